@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use image::Pixel;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::modern_skin::convert_legacy_layout;
+use crate::skin_layout::{BodyPart, Layer};
+
+/// Folder next to the skins holding armor textures, laid out like the game's
+/// `textures/models/armor` such as `diamond_layer_1.png`
+pub const ARMOR_DIR: &str = "armor";
+
+/// Color of undyed leather armor
+const DEFAULT_LEATHER_COLOR: [u8; 3] = [0xa0, 0x65, 0x40];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArmorMaterial {
+    Leather,
+    Chainmail,
+    Iron,
+    Gold,
+    Diamond,
+    Netherite,
+}
+
+impl ArmorMaterial {
+    fn texture_name(&self) -> &'static str {
+        match self {
+            ArmorMaterial::Leather => "leather",
+            ArmorMaterial::Chainmail => "chainmail",
+            ArmorMaterial::Iron => "iron",
+            ArmorMaterial::Gold => "gold",
+            ArmorMaterial::Diamond => "diamond",
+            ArmorMaterial::Netherite => "netherite",
+        }
+    }
+}
+
+/// Armor drawn over a skin's overlay, each piece is left off if not set
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Armor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub helmet: Option<ArmorMaterial>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chestplate: Option<ArmorMaterial>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leggings: Option<ArmorMaterial>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boots: Option<ArmorMaterial>,
+
+    /// Dye color of leather armor as `[red, green, blue]`, undyed brown if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leather_color: Option<[u8; 3]>,
+}
+
+fn load_png(path: &Path) -> Result<image::RgbaImage> {
+    Ok(image::open(path)?.into_rgba8())
+}
+
+impl Armor {
+    pub fn is_empty(&self) -> bool {
+        self.helmet.is_none() && self.chestplate.is_none() && self.leggings.is_none() && self.boots.is_none()
+    }
+
+    /// Load an armor layer texture (1 or 2) from `dir`, dyed if it's leather and laid out like a
+    /// modern skin at `size` pixels wide
+    fn load_layer(&self, dir: &Path, material: ArmorMaterial, layer: u8, size: u32) -> Result<image::RgbaImage> {
+        let name = format!("{}_layer_{}", material.texture_name(), layer);
+        let mut texture = load_png(&dir.join(format!("{}.png", name)))?;
+
+        let (width, height) = texture.dimensions();
+        if width < 64 || width % 64 != 0 || width != height * 2 {
+            return Err(Error::InvalidSize { width, height });
+        }
+
+        if material == ArmorMaterial::Leather {
+            let color = self.leather_color.unwrap_or(DEFAULT_LEATHER_COLOR);
+            for pixel in texture.pixels_mut() {
+                for (channel, &dye) in pixel.channels_mut()[..3].iter_mut().zip(&color) {
+                    *channel = ((*channel as u32 * dye as u32) / 255) as u8;
+                }
+            }
+
+            // the parts of leather armor that aren't dyed
+            let overlay_path = dir.join(format!("{}_overlay.png", name));
+            if overlay_path.exists() {
+                let overlay = load_png(&overlay_path)?;
+                if overlay.dimensions() == texture.dimensions() {
+                    image::imageops::overlay(&mut texture, &overlay, 0, 0);
+                }
+            }
+        }
+
+        // armor isn't a skin, so an opaque helmet layer is kept
+        let texture = convert_legacy_layout(&texture);
+        if texture.width() == size {
+            Ok(texture)
+        } else {
+            Ok(image::imageops::resize(&texture, size, size, image::imageops::Nearest))
+        }
+    }
+
+    /// Draw the armor over the overlay of a modern layout skin, with textures from `dir`
+    pub fn composite(&self, skin: &mut image::RgbaImage, dir: &Path) -> Result<()> {
+        let size = skin.width();
+        let scale = size / 64;
+
+        // inner pieces first so the outer ones are drawn over them
+        let pieces = [
+            (self.leggings, 2, &[BodyPart::Body, BodyPart::RightLeg, BodyPart::LeftLeg][..]),
+            (self.boots, 1, &[BodyPart::RightLeg, BodyPart::LeftLeg][..]),
+            (self.chestplate, 1, &[BodyPart::Body, BodyPart::RightArm, BodyPart::LeftArm][..]),
+            (self.helmet, 1, &[BodyPart::Head][..]),
+        ];
+
+        for &(material, layer, parts) in &pieces {
+            let material = match material {
+                Some(material) => material,
+                None => continue,
+            };
+
+            let texture = self.load_layer(dir, material, layer, size)?;
+
+            for &part in parts {
+                let mut faces: Vec<_> = part.faces(Layer::Base).iter().zip(part.faces(Layer::Overlay).iter()).map(|(&from, &to)| (from, to)).collect();
+
+                // the helmet's own outer layer is drawn too
+                if part == BodyPart::Head {
+                    faces.extend(part.faces(Layer::Overlay).iter().map(|&face| (face, face)));
+                }
+
+                for (from, to) in faces {
+                    let (from, to) = (from.scaled(scale), to.scaled(scale));
+
+                    for y in 0..from.height {
+                        for x in 0..from.width {
+                            let armor = *texture.get_pixel(from.x + x, from.y + y);
+                            skin.get_pixel_mut(to.x + x, to.y + y).blend(&armor);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use crate::color_correct::{default_contrast, luminance, srgb_to_linear, ColorProfile};
+use crate::skin_layout::face_mask;
+
+/// Linear luminance of middle gray, the brightness skins are nudged towards
+const MIDDLE_GRAY: f64 = 0.18;
+
+/// How much of the way to middle gray the median brightness is moved, so dark skins stay dark
+const EXPOSURE_STRENGTH: f64 = 0.5;
+const MAX_EXPOSURE: f64 = 4.0;
+
+/// Range in stops between the darkest and brightest parts of a skin after correction
+const MIN_RANGE: f64 = 3.0;
+const MAX_RANGE: f64 = 8.0;
+
+const MIN_CONTRAST: f64 = 0.5;
+const MAX_CONTRAST: f64 = 1.5;
+
+/// Settings for the linear color profile picked for a skin from its brightness
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct AutoExposure {
+    pub exposure: f64,
+    pub contrast: f64,
+}
+
+/// Value at a fraction of the way through sorted values
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * fraction).round() as usize]
+}
+
+impl AutoExposure {
+    /// Pick settings from the histogram of the visible pixels of a modern layout skin. Dark
+    /// skins are brightened with their shadows spread out, bright skins are darkened.
+    pub fn analyze(skin: &image::RgbaImage) -> Self {
+        let on_face = face_mask(skin.width() / 64);
+
+        // brightness in stops, with black treated as 10 stops below white
+        let mut stops: Vec<f64> = skin
+            .pixels()
+            .zip(on_face)
+            .filter(|&(pixel, on_face)| on_face && pixel[3] != 0)
+            .map(|(pixel, _)| {
+                let color = [
+                    srgb_to_linear(pixel[0] as f64 / 255.0),
+                    srgb_to_linear(pixel[1] as f64 / 255.0),
+                    srgb_to_linear(pixel[2] as f64 / 255.0),
+                ];
+
+                luminance(color).max(1.0 / 1024.0).log2()
+            })
+            .collect();
+
+        if stops.is_empty() {
+            return AutoExposure { exposure: 0.0, contrast: default_contrast() }
+        }
+
+        stops.sort_by(|x, y| x.partial_cmp(y).unwrap());
+
+        let median = percentile(&stops, 0.5);
+        let range = percentile(&stops, 0.95) - percentile(&stops, 0.05);
+
+        let mut contrast = default_contrast();
+        if range > 0.0 {
+            if contrast * range < MIN_RANGE {
+                contrast = MIN_RANGE / range;
+            } else if contrast * range > MAX_RANGE {
+                contrast = MAX_RANGE / range;
+            }
+        }
+
+        let contrast = contrast.clamp(MIN_CONTRAST, MAX_CONTRAST);
+
+        // contrast is applied relative to white, so pick the exposure that puts the median
+        // where the default contrast would have after moving it towards middle gray
+        let target = default_contrast() * (median + ((MIDDLE_GRAY.log2() - median) * EXPOSURE_STRENGTH));
+        let exposure = ((target / contrast) - median).clamp(-MAX_EXPOSURE, MAX_EXPOSURE);
+
+        AutoExposure { exposure, contrast }
+    }
+
+    pub fn profile(&self) -> ColorProfile {
+        ColorProfile::linear(self.exposure, self.contrast)
+    }
+}
@@ -0,0 +1,748 @@
+//! BC1, BC3 and BC7 block compression.
+//!
+//! Textures are split into 4x4 pixel blocks, each stored as two endpoint colors and an index
+//! per pixel picking a color between them. Endpoints are picked along the line that best fits
+//! the block's colors, which works well for the few colors in each block of a skin.
+//!
+//! Only BC7 mode 6 is used when compressing, but every BC7 mode can be decompressed so
+//! textures made by other tools can be read.
+
+/// The 16 pixels of a block, in rows
+type Block = [[u8; 4]; 16];
+
+/// BC7 interpolation weights (out of 64) for 4 bit indices
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// BC7 interpolation weights for 2 and 3 bit indices
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+
+/// Subset of each pixel in the BC7 partitions with 2 subsets, bit `i` for pixel `i`
+const BC7_PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80,
+    0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00, 0xfff0, 0xf000,
+    0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c,
+    0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a,
+    0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// Subset of each pixel in the BC7 partitions with 3 subsets, bits `2i` and `2i + 1` for pixel `i`
+const BC7_PARTITIONS_3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+/// The pixel of the second subset whose index is stored without its highest bit, in each
+/// partition with 2 subsets. The first subset's is always pixel 0.
+const BC7_ANCHORS_2: [usize; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// The same for the second and third subsets of each partition with 3 subsets
+const BC7_ANCHORS_3: [[usize; 2]; 64] = [
+    [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8],
+    [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8],
+    [3, 15], [3, 8], [8, 15], [15, 3], [3, 15], [3, 8], [6, 15], [10, 8],
+    [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8],
+    [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+    [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8],
+    [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15],
+    [5, 15], [10, 15], [8, 15], [13, 15], [15, 3], [12, 15], [3, 15], [3, 8],
+];
+
+/// How a BC7 mode stores the p-bits, the shared lowest bit of endpoint channels
+#[derive(PartialEq)]
+enum PBits {
+    None,
+    PerEndpoint,
+    PerSubset,
+}
+
+/// Layout of a BC7 block in each mode, in bits
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: usize,
+    rotation_bits: usize,
+    index_selection_bits: usize,
+    color_bits: usize,
+    alpha_bits: usize,
+    p_bits: PBits,
+    index_bits: usize,
+    alpha_index_bits: usize,
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, p_bits: PBits::PerEndpoint, index_bits: 3, alpha_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, p_bits: PBits::PerSubset, index_bits: 3, alpha_index_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, p_bits: PBits::None, index_bits: 2, alpha_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, p_bits: PBits::PerEndpoint, index_bits: 2, alpha_index_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, p_bits: PBits::None, index_bits: 2, alpha_index_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, p_bits: PBits::None, index_bits: 2, alpha_index_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, p_bits: PBits::PerEndpoint, index_bits: 4, alpha_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, p_bits: PBits::PerEndpoint, index_bits: 2, alpha_index_bits: 0 },
+];
+
+fn get_block(image: &image::RgbaImage, block_x: u32, block_y: u32) -> Block {
+    let (width, height) = image.dimensions();
+    let mut block = [[0; 4]; 16];
+
+    for (i, pixel) in block.iter_mut().enumerate() {
+        let x = ((block_x * 4) + (i as u32 % 4)).min(width - 1);
+        let y = ((block_y * 4) + (i as u32 / 4)).min(height - 1);
+
+        *pixel = image.get_pixel(x, y).0;
+    }
+
+    block
+}
+
+/// Compress an image block by block, in rows of blocks
+fn compress<B: AsRef<[u8]>>(image: &image::RgbaImage, encode_block: impl Fn(&Block) -> B) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let (width_in_blocks, height_in_blocks) = ((width + 3) / 4, (height + 3) / 4);
+
+    let mut data = Vec::new();
+    for block_y in 0..height_in_blocks {
+        for block_x in 0..width_in_blocks {
+            data.extend_from_slice(encode_block(&get_block(image, block_x, block_y)).as_ref());
+        }
+    }
+
+    data
+}
+
+/// Decompress rows of blocks into RGBA pixels, leaving out the parts of the blocks on the
+/// right and bottom edges past the image
+fn decompress(data: &[u8], width: u32, height: u32, bytes_per_block: usize, decode_block: impl Fn(&[u8]) -> Block) -> Vec<u8> {
+    let (width_in_blocks, height_in_blocks) = ((width + 3) / 4, (height + 3) / 4);
+
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    let blocks = data.chunks_exact(bytes_per_block).take((width_in_blocks * height_in_blocks) as usize);
+    for (i, block) in blocks.enumerate() {
+        let (block_x, block_y) = (i as u32 % width_in_blocks, i as u32 / width_in_blocks);
+
+        for (j, pixel) in decode_block(block).iter().enumerate() {
+            let x = (block_x * 4) + (j as u32 % 4);
+            let y = (block_y * 4) + (j as u32 / 4);
+
+            if x < width && y < height {
+                let offset = ((y * width + x) * 4) as usize;
+                pixels[offset..offset + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+
+    pixels
+}
+
+fn distance(x: &[f32], y: &[f32]) -> f32 {
+    x.iter().zip(y).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// Endpoints of the line through the colors (using the first `channels` channels) that best
+/// fits them, found from the principal axis of the colors
+fn fit_endpoints(colors: &[[f32; 4]], channels: usize) -> ([f32; 4], [f32; 4]) {
+    let mut mean = [0.0; 4];
+    for color in colors {
+        for c in 0..channels {
+            mean[c] += color[c] / colors.len() as f32;
+        }
+    }
+
+    let mut covariance = [[0.0f32; 4]; 4];
+    for color in colors {
+        for i in 0..channels {
+            for j in 0..channels {
+                covariance[i][j] += (color[i] - mean[i]) * (color[j] - mean[j]);
+            }
+        }
+    }
+
+    // power iteration, starting from the diagonal of the bounding box
+    let mut axis = [0.0; 4];
+    for c in 0..channels {
+        let min = colors.iter().map(|color| color[c]).fold(f32::MAX, f32::min);
+        let max = colors.iter().map(|color| color[c]).fold(f32::MIN, f32::max);
+        axis[c] = max - min;
+    }
+
+    for _ in 0..8 {
+        let mut next = [0.0; 4];
+        for i in 0..channels {
+            for j in 0..channels {
+                next[i] += covariance[i][j] * axis[j];
+            }
+        }
+
+        let length = next.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break
+        }
+
+        for c in 0..channels {
+            axis[c] = next[c] / length;
+        }
+    }
+
+    let length = axis.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if length < 1e-6 {
+        return (mean, mean)
+    }
+
+    let project = |color: &[f32; 4]| (0..channels).map(|c| (color[c] - mean[c]) * axis[c] / length).sum::<f32>();
+    let min = colors.iter().map(project).fold(f32::MAX, f32::min);
+    let max = colors.iter().map(project).fold(f32::MIN, f32::max);
+
+    let mut start = [0.0; 4];
+    let mut end = [0.0; 4];
+    for c in 0..channels {
+        start[c] = (mean[c] + (axis[c] / length * min)).clamp(0.0, 255.0);
+        end[c] = (mean[c] + (axis[c] / length * max)).clamp(0.0, 255.0);
+    }
+
+    (start, end)
+}
+
+fn to_floats(pixel: &[u8; 4]) -> [f32; 4] {
+    [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32, pixel[3] as f32]
+}
+
+fn to_565(color: [f32; 4]) -> u16 {
+    let r = (color[0] * 31.0 / 255.0).round() as u16;
+    let g = (color[1] * 63.0 / 255.0).round() as u16;
+    let b = (color[2] * 31.0 / 255.0).round() as u16;
+
+    (r << 11) | (g << 5) | b
+}
+
+fn from_565(color: u16) -> [f32; 3] {
+    let (r, g, b) = ((color >> 11) & 31, (color >> 5) & 63, color & 31);
+
+    [
+        ((r << 3) | (r >> 2)) as f32,
+        ((g << 2) | (g >> 4)) as f32,
+        ((b << 3) | (b >> 2)) as f32,
+    ]
+}
+
+fn lerp(start: [f32; 3], end: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        start[0] + (end[0] - start[0]) * t,
+        start[1] + (end[1] - start[1]) * t,
+        start[2] + (end[2] - start[2]) * t,
+    ]
+}
+
+/// Encode the colors of a block as a BC1 block. If `punch_through` is set, pixels less than
+/// half opaque are made transparent using BC1's 3 color mode.
+fn encode_bc1_colors(block: &Block, punch_through: bool) -> [u8; 8] {
+    let transparent = |pixel: &[u8; 4]| punch_through && pixel[3] < 128;
+    let has_transparency = block.iter().any(transparent);
+
+    let colors: Vec<[f32; 4]> = block.iter().filter(|pixel| !transparent(pixel)).map(to_floats).collect();
+    let (start, end) = if colors.is_empty() {
+        ([0.0; 4], [0.0; 4])
+    } else {
+        fit_endpoints(&colors, 3)
+    };
+
+    let (mut color0, mut color1) = (to_565(start), to_565(end));
+
+    // color0 > color1 selects 4 color mode, otherwise 3 colors and transparent black
+    if has_transparency == (color0 > color1) {
+        std::mem::swap(&mut color0, &mut color1);
+    }
+
+    let (c0, c1) = (from_565(color0), from_565(color1));
+    let palette = if has_transparency || color0 == color1 {
+        vec![c0, c1, lerp(c0, c1, 0.5)]
+    } else {
+        vec![c0, c1, lerp(c0, c1, 1.0 / 3.0), lerp(c0, c1, 2.0 / 3.0)]
+    };
+
+    let mut indices = 0u32;
+    for (i, pixel) in block.iter().enumerate() {
+        let index = if transparent(pixel) {
+            3
+        } else {
+            let color = to_floats(pixel);
+            (0..palette.len())
+                .min_by(|&x, &y| distance(&palette[x], &color[..3]).partial_cmp(&distance(&palette[y], &color[..3])).unwrap())
+                .unwrap() as u32
+        };
+
+        indices |= index << (i * 2);
+    }
+
+    let mut encoded = [0; 8];
+    encoded[0..2].copy_from_slice(&color0.to_le_bytes());
+    encoded[2..4].copy_from_slice(&color1.to_le_bytes());
+    encoded[4..8].copy_from_slice(&indices.to_le_bytes());
+
+    encoded
+}
+
+/// Encode the alpha of a block as a BC3 alpha block, interpolating 8 levels between the
+/// most and least opaque pixels
+fn encode_bc3_alpha(block: &Block) -> [u8; 8] {
+    let max = block.iter().map(|pixel| pixel[3]).max().unwrap();
+    let min = block.iter().map(|pixel| pixel[3]).min().unwrap();
+
+    let mut encoded = [0; 8];
+    encoded[0] = max;
+    encoded[1] = min;
+
+    if max == min {
+        return encoded
+    }
+
+    let mut palette = [max as u32, min as u32, 0, 0, 0, 0, 0, 0];
+    for (i, level) in palette.iter_mut().enumerate().skip(2) {
+        *level = (((8 - i as u32) * max as u32) + ((i as u32 - 1) * min as u32)) / 7;
+    }
+
+    let mut indices = 0u64;
+    for (i, pixel) in block.iter().enumerate() {
+        let alpha = pixel[3] as i32;
+        let index = (0..8).min_by_key(|&x| (palette[x] as i32 - alpha).abs()).unwrap() as u64;
+
+        indices |= index << (i * 3);
+    }
+
+    encoded[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+
+    encoded
+}
+
+fn encode_bc1(block: &Block) -> [u8; 8] {
+    encode_bc1_colors(block, true)
+}
+
+fn encode_bc3(block: &Block) -> [u8; 16] {
+    let mut encoded = [0; 16];
+    encoded[..8].copy_from_slice(&encode_bc3_alpha(block));
+    encoded[8..].copy_from_slice(&encode_bc1_colors(block, false));
+
+    encoded
+}
+
+/// Decode a BC1 color block. If `punch_through` is set, BC1's 3 color mode with transparent
+/// black is used when the first endpoint isn't larger than the second, like for BC1 textures.
+fn decode_bc1_colors(block: &[u8], punch_through: bool) -> Block {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    let opaque = |color: [f32; 3]| [color[0].round() as u8, color[1].round() as u8, color[2].round() as u8, 255];
+
+    let (c0, c1) = (from_565(color0), from_565(color1));
+    let palette = if punch_through && color0 <= color1 {
+        [opaque(c0), opaque(c1), opaque(lerp(c0, c1, 0.5)), [0; 4]]
+    } else {
+        [opaque(c0), opaque(c1), opaque(lerp(c0, c1, 1.0 / 3.0)), opaque(lerp(c0, c1, 2.0 / 3.0))]
+    };
+
+    let mut decoded = [[0; 4]; 16];
+    for (i, pixel) in decoded.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (i * 2)) & 3) as usize];
+    }
+
+    decoded
+}
+
+/// Decode a BC3 alpha block into the alpha of each pixel
+fn decode_bc3_alpha(block: &[u8]) -> [u8; 16] {
+    let (max, min) = (block[0] as u32, block[1] as u32);
+
+    // 8 levels if the first endpoint is larger, otherwise 6 levels and fully transparent and opaque
+    let mut palette = [max, min, 0, 0, 0, 0, 0, 255];
+    if max > min {
+        for (i, level) in palette.iter_mut().enumerate().skip(2) {
+            *level = (((8 - i as u32) * max) + ((i as u32 - 1) * min)) / 7;
+        }
+    } else {
+        for (i, level) in palette.iter_mut().enumerate().take(6).skip(2) {
+            *level = (((6 - i as u32) * max) + ((i as u32 - 1) * min)) / 5;
+        }
+    }
+
+    let mut index_bytes = [0; 8];
+    index_bytes[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(index_bytes);
+
+    let mut alpha = [0; 16];
+    for (i, alpha) in alpha.iter_mut().enumerate() {
+        *alpha = palette[((indices >> (i * 3)) & 7) as usize] as u8;
+    }
+
+    alpha
+}
+
+fn decode_bc1(block: &[u8]) -> Block {
+    decode_bc1_colors(block, true)
+}
+
+fn decode_bc3(block: &[u8]) -> Block {
+    let mut decoded = decode_bc1_colors(&block[8..16], false);
+    for (pixel, alpha) in decoded.iter_mut().zip(decode_bc3_alpha(&block[..8])) {
+        pixel[3] = alpha;
+    }
+
+    decoded
+}
+
+/// Writes bits into a block starting from the lowest bit
+struct BitWriter {
+    bytes: [u8; 16],
+    position: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: usize) {
+        for bit in 0..bits {
+            if value & (1 << bit) != 0 {
+                self.bytes[self.position / 8] |= 1 << (self.position % 8);
+            }
+
+            self.position += 1;
+        }
+    }
+}
+
+/// Reads bits from a block starting from the lowest bit
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: usize) -> u32 {
+        let mut value = 0;
+        for bit in 0..bits {
+            if self.bytes[self.position / 8] & (1 << (self.position % 8)) != 0 {
+                value |= 1 << bit;
+            }
+
+            self.position += 1;
+        }
+
+        value
+    }
+}
+
+/// Quantize an endpoint to 7 bits per channel plus a shared lowest bit, returning the 7 bit
+/// channels and the shared bit
+fn quantize_bc7_endpoint(endpoint: [f32; 4]) -> ([u32; 4], u32) {
+    let quantize = |p: u32| {
+        let mut channels = [0; 4];
+        let mut error = 0.0;
+
+        for c in 0..4 {
+            channels[c] = ((endpoint[c] - p as f32) / 2.0).round().clamp(0.0, 127.0) as u32;
+            error += (endpoint[c] - ((channels[c] << 1) | p) as f32).powi(2);
+        }
+
+        (channels, error)
+    };
+
+    let (channels0, error0) = quantize(0);
+    let (channels1, error1) = quantize(1);
+
+    if error0 <= error1 {
+        (channels0, 0)
+    } else {
+        (channels1, 1)
+    }
+}
+
+/// Pick the closest of the 16 levels between two quantized BC7 endpoints for each color,
+/// returning the indices and the total squared error
+fn bc7_indices(colors: &[[f32; 4]], endpoints: &[([u32; 4], u32); 2]) -> ([u32; 16], f32) {
+    let unquantize = |&(channels, p): &([u32; 4], u32)| {
+        let mut color = [0; 4];
+        for c in 0..4 {
+            color[c] = (channels[c] << 1) | p;
+        }
+
+        color
+    };
+
+    let (e0, e1) = (unquantize(&endpoints[0]), unquantize(&endpoints[1]));
+    let palette: Vec<[f32; 4]> = BC7_WEIGHTS
+        .iter()
+        .map(|&weight| {
+            let mut color = [0.0; 4];
+            for c in 0..4 {
+                color[c] = ((((64 - weight) * e0[c]) + (weight * e1[c]) + 32) >> 6) as f32;
+            }
+
+            color
+        })
+        .collect();
+
+    let mut indices = [0u32; 16];
+    let mut error = 0.0;
+    for (index, color) in indices.iter_mut().zip(colors) {
+        *index = (0..16)
+            .min_by(|&x, &y| distance(&palette[x], color).partial_cmp(&distance(&palette[y], color)).unwrap())
+            .unwrap() as u32;
+
+        error += distance(&palette[*index as usize], color);
+    }
+
+    (indices, error)
+}
+
+/// Least squares fit of the endpoints to the colors given the level picked for each color,
+/// returns `None` if every color is on the same level
+fn refine_bc7_endpoints(colors: &[[f32; 4]], indices: &[u32; 16]) -> Option<([f32; 4], [f32; 4])> {
+    let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+    let mut color_a = [0.0; 4];
+    let mut color_b = [0.0; 4];
+
+    for (color, &index) in colors.iter().zip(indices) {
+        let weight = BC7_WEIGHTS[index as usize] as f32 / 64.0;
+        let (a, b) = (1.0 - weight, weight);
+
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+
+        for c in 0..4 {
+            color_a[c] += a * color[c];
+            color_b[c] += b * color[c];
+        }
+    }
+
+    let determinant = (aa * bb) - (ab * ab);
+    if determinant.abs() < 1e-6 {
+        return None
+    }
+
+    let mut start = [0.0; 4];
+    let mut end = [0.0; 4];
+    for c in 0..4 {
+        start[c] = (((bb * color_a[c]) - (ab * color_b[c])) / determinant).clamp(0.0, 255.0);
+        end[c] = (((aa * color_b[c]) - (ab * color_a[c])) / determinant).clamp(0.0, 255.0);
+    }
+
+    Some((start, end))
+}
+
+/// Encode a block as a BC7 mode 6 block, a single pair of RGBA endpoints with 16 levels
+fn encode_bc7(block: &Block) -> [u8; 16] {
+    let colors: Vec<[f32; 4]> = block.iter().map(to_floats).collect();
+    let (start, end) = fit_endpoints(&colors, 4);
+
+    let mut endpoints = [quantize_bc7_endpoint(start), quantize_bc7_endpoint(end)];
+    let (mut indices, mut error) = bc7_indices(&colors, &endpoints);
+
+    for _ in 0..2 {
+        let (start, end) = match refine_bc7_endpoints(&colors, &indices) {
+            Some(refined) => refined,
+            None => break,
+        };
+
+        let refined = [quantize_bc7_endpoint(start), quantize_bc7_endpoint(end)];
+        let (refined_indices, refined_error) = bc7_indices(&colors, &refined);
+        if refined_error >= error {
+            break
+        }
+
+        endpoints = refined;
+        indices = refined_indices;
+        error = refined_error;
+    }
+
+    // the first index is stored without its highest bit, so it has to be in the lower half
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        for index in indices.iter_mut() {
+            *index = 15 - *index;
+        }
+    }
+
+    let mut writer = BitWriter { bytes: [0; 16], position: 0 };
+
+    // mode 6
+    writer.write(1 << 6, 7);
+
+    for c in 0..4 {
+        writer.write(endpoints[0].0[c], 7);
+        writer.write(endpoints[1].0[c], 7);
+    }
+
+    writer.write(endpoints[0].1, 1);
+    writer.write(endpoints[1].1, 1);
+
+    writer.write(indices[0], 3);
+    for &index in &indices[1..] {
+        writer.write(index, 4);
+    }
+
+    writer.bytes
+}
+
+fn bc7_weights(index_bits: usize) -> &'static [u32] {
+    match index_bits {
+        2 => &BC7_WEIGHTS_2,
+        3 => &BC7_WEIGHTS_3,
+        _ => &BC7_WEIGHTS,
+    }
+}
+
+/// Decode a BC7 block in any mode
+fn decode_bc7(block: &[u8]) -> Block {
+    // the mode is the number of zero bits before the first set bit, blocks without one are reserved
+    let mode_number = block[0].trailing_zeros() as usize;
+    if mode_number >= 8 {
+        return [[0; 4]; 16]
+    }
+
+    let mode = &BC7_MODES[mode_number];
+    let mut reader = BitReader { bytes: block, position: mode_number + 1 };
+
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    // the first and second endpoint of each subset, with each channel stored for every
+    // endpoint before the next channel
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for c in 0..3 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[c] = reader.read(mode.color_bits);
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        endpoint[3] = reader.read(mode.alpha_bits);
+    }
+
+    let mut p_bits = [0; 6];
+    match mode.p_bits {
+        PBits::None => (),
+        PBits::PerEndpoint => {
+            for p in p_bits.iter_mut().take(endpoint_count) {
+                *p = reader.read(1);
+            }
+        }
+        PBits::PerSubset => {
+            for subset in 0..mode.subsets {
+                let p = reader.read(1);
+                p_bits[subset * 2] = p;
+                p_bits[subset * 2 + 1] = p;
+            }
+        }
+    }
+
+    // expand each channel to 8 bits by repeating its highest bits, without alpha it's opaque
+    for (endpoint, &p) in endpoints.iter_mut().zip(&p_bits).take(endpoint_count) {
+        for (c, channel) in endpoint.iter_mut().enumerate() {
+            let bits = if c < 3 { mode.color_bits } else { mode.alpha_bits };
+            if bits == 0 {
+                *channel = 255;
+                continue
+            }
+
+            let (value, bits) = if mode.p_bits == PBits::None {
+                (*channel, bits)
+            } else {
+                ((*channel << 1) | p, bits + 1)
+            };
+
+            *channel = (value << (8 - bits)) | (value >> (2 * bits - 8));
+        }
+    }
+
+    let subset = |i: usize| match mode.subsets {
+        2 => ((BC7_PARTITIONS_2[partition] >> i) & 1) as usize,
+        3 => ((BC7_PARTITIONS_3[partition] >> (i * 2)) & 3) as usize,
+        _ => 0,
+    };
+
+    // the first pixel of each subset is stored without its highest bit
+    let is_anchor = |i: usize| match mode.subsets {
+        2 => i == 0 || i == BC7_ANCHORS_2[partition],
+        3 => i == 0 || BC7_ANCHORS_3[partition].contains(&i),
+        _ => i == 0,
+    };
+
+    let mut indices = [0; 16];
+    for (i, index) in indices.iter_mut().enumerate() {
+        *index = reader.read(mode.index_bits - is_anchor(i) as usize) as usize;
+    }
+
+    // modes 4 and 5 have a second set of indices for alpha
+    let mut alpha_indices = (indices, mode.index_bits);
+    if mode.alpha_index_bits > 0 {
+        alpha_indices.1 = mode.alpha_index_bits;
+        for (i, index) in alpha_indices.0.iter_mut().enumerate() {
+            *index = reader.read(mode.alpha_index_bits - (i == 0) as usize) as usize;
+        }
+    }
+
+    // the index selection bit swaps which indices are used for color and alpha
+    let color_indices = (indices, mode.index_bits);
+    let (color_indices, alpha_indices) = if index_selection == 0 {
+        (color_indices, alpha_indices)
+    } else {
+        (alpha_indices, color_indices)
+    };
+
+    let (color_weights, alpha_weights) = (bc7_weights(color_indices.1), bc7_weights(alpha_indices.1));
+
+    let mut decoded = [[0; 4]; 16];
+    for (i, pixel) in decoded.iter_mut().enumerate() {
+        let (e0, e1) = (endpoints[subset(i) * 2], endpoints[subset(i) * 2 + 1]);
+
+        for c in 0..4 {
+            let weight = if c < 3 { color_weights[color_indices.0[i]] } else { alpha_weights[alpha_indices.0[i]] };
+            pixel[c] = ((((64 - weight) * e0[c]) + (weight * e1[c]) + 32) >> 6) as u8;
+        }
+
+        // the rotation swaps alpha with one of the colors
+        if rotation > 0 {
+            pixel.swap(rotation as usize - 1, 3);
+        }
+    }
+
+    decoded
+}
+
+pub fn compress_bc1(image: &image::RgbaImage) -> Vec<u8> {
+    compress(image, encode_bc1)
+}
+
+pub fn compress_bc3(image: &image::RgbaImage) -> Vec<u8> {
+    compress(image, encode_bc3)
+}
+
+pub fn compress_bc7(image: &image::RgbaImage) -> Vec<u8> {
+    compress(image, encode_bc7)
+}
+
+pub fn decompress_bc1(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    decompress(data, width, height, 8, decode_bc1)
+}
+
+pub fn decompress_bc3(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    decompress(data, width, height, 16, decode_bc3)
+}
+
+pub fn decompress_bc7(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    decompress(data, width, height, 16, decode_bc7)
+}
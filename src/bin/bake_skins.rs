@@ -0,0 +1,191 @@
+//! Bake skins into a static ARCropolis mod folder, for players not running the plugin.
+//!
+//! ```text
+//! bake_skins [--name <mod name>] [--base <2 layer mod folder>] [--config <config.json>] <output folder> <slot>=<skin.png>...
+//! ```
+//!
+//! Slots are given as `0` through `7` or `c00` through `c07`. Slim skins are picked up from a
+//! json file next to the png, the same as in the plugin's cache. The Steve model files for each
+//! slot are copied from the base mod folder (`ultimate/mods/minecraft_2_layer` by default).
+//! Texture settings are read from the same `config.json` the plugin uses if one is given.
+
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use minecraft_skins::config::Config;
+use minecraft_skins::error::Result;
+use minecraft_skins::skin_texture::{load_skin, encode_skin_nutexb, encode_bntx};
+use minecraft_skins::stock_generation::gen_stock_image;
+
+#[cfg(feature = "renders")]
+use minecraft_skins::chara_renders::{create_render, create_chara_image, CharaImage};
+
+const DEFAULT_BASE: &str = "ultimate/mods/minecraft_2_layer";
+const DEFAULT_NAME: &str = "Minecraft Skins";
+const SKIN_NUTEXB: &str = "def_pickel_001_col.nutexb";
+
+const USAGE: &str = "Usage: bake_skins [--name <mod name>] [--base <2 layer mod folder>] [--config <config.json>] <output folder> <slot>=<skin.png>...";
+
+struct Args {
+    name: String,
+    base: PathBuf,
+    config: Config,
+    output: PathBuf,
+    skins: Vec<(usize, PathBuf)>,
+}
+
+fn parse_slot(slot: &str) -> Option<usize> {
+    let slot = slot.strip_prefix('c').unwrap_or(slot);
+
+    slot.parse().ok().filter(|&slot| slot < 8)
+}
+
+fn parse_args() -> Option<Args> {
+    let mut name = DEFAULT_NAME.to_owned();
+    let mut base = PathBuf::from(DEFAULT_BASE);
+    let mut config = Config::default();
+    let mut output = None;
+    let mut skins = vec![];
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--name" => name = args.next()?,
+            "--base" => base = args.next()?.into(),
+            "--config" => config = Config::load(Path::new(&args.next()?)),
+            _ if output.is_none() => output = Some(PathBuf::from(arg)),
+            _ => {
+                let mut split = arg.splitn(2, '=');
+                let slot = parse_slot(split.next()?)?;
+                let skin = PathBuf::from(split.next()?);
+
+                skins.push((slot, skin));
+            }
+        }
+    }
+
+    if skins.is_empty() {
+        return None
+    }
+
+    Some(Args { name, base, config, output: output?, skins })
+}
+
+fn write_file(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, data)?;
+
+    Ok(())
+}
+
+fn write_bntx_file(path: &Path, image: image::RgbaImage) -> Result<()> {
+    let mut writer = Cursor::new(Vec::new());
+
+    encode_bntx(image, &mut writer)?;
+
+    write_file(path, writer.get_ref())
+}
+
+/// Copy the 2 layer Steve model for a slot, everything but the skin texture
+fn copy_model(base: &Path, output: &Path, slot: usize) -> Result<()> {
+    let model_dir = Path::new("fighter/pickel/model/body").join(format!("c{:02}", slot));
+
+    let from = base.join(&model_dir);
+    if !from.is_dir() {
+        println!("Warning: no model for c{:02} in {}, skipping", slot, base.display());
+        return Ok(())
+    }
+
+    for entry in fs::read_dir(&from)? {
+        let path = entry?.path();
+        let file_name = match path.file_name() {
+            Some(name) if name != SKIN_NUTEXB && path.is_file() => name.to_owned(),
+            _ => continue,
+        };
+
+        write_file(&output.join(&model_dir).join(file_name), &fs::read(&path)?)?;
+    }
+
+    Ok(())
+}
+
+fn bake_skin(args: &Args, slot: usize, skin_path: &Path) -> Result<()> {
+    let skin = load_skin(skin_path)?;
+    let colors = args.config.skin_color_correction(skin_path, &skin)?;
+
+    copy_model(&args.base, &args.output, slot)?;
+
+    let mut nutexb = Cursor::new(Vec::new());
+    encode_skin_nutexb(skin.clone(), &args.config.texture, &colors, &mut nutexb)?;
+    write_file(
+        &args.output.join(format!("fighter/pickel/model/body/c{:02}/{}", slot, SKIN_NUTEXB)),
+        nutexb.get_ref(),
+    )?;
+
+    write_bntx_file(
+        &args.output.join(format!("ui/replace_patch/chara/chara_2/chara_2_pickel_{:02}.bntx", slot)),
+        gen_stock_image(&skin, &args.config.stock_icon),
+    )?;
+
+    #[cfg(feature = "renders")] {
+        let render = create_render(&skin, &colors);
+
+        for &chara in &CharaImage::ALL {
+            write_bntx_file(
+                &args.output.join(format!("ui/replace_patch/chara/{0}/{0}_pickel_{1:02}.bntx", chara.name(), slot)),
+                create_chara_image(&render, chara),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_info_toml(args: &Args) -> Result<()> {
+    let slots = args.skins
+        .iter()
+        .map(|(slot, _)| format!("c{:02}", slot))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let info = format!(
+        "display_name = {:?}\n\
+         description = \"Minecraft skins for Steve ({}), generated by bake_skins\"\n\
+         version = \"{}\"\n\
+         category = \"Fighter\"\n",
+        args.name,
+        slots,
+        env!("CARGO_PKG_VERSION"),
+    );
+
+    write_file(&args.output.join("info.toml"), info.as_bytes())
+}
+
+fn main() {
+    let args = match parse_args() {
+        Some(args) => args,
+        None => {
+            eprintln!("{}", USAGE);
+            exit(1);
+        }
+    };
+
+    for (slot, skin_path) in &args.skins {
+        if let Err(err) = bake_skin(&args, *slot, skin_path) {
+            eprintln!("Error: failed to bake {} into c{:02}: {}", skin_path.display(), slot, err);
+            exit(1);
+        }
+
+        println!("Baked {} into c{:02}", skin_path.display(), slot);
+    }
+
+    if let Err(err) = write_info_toml(&args) {
+        eprintln!("Error: failed to write info.toml: {}", err);
+        exit(1);
+    }
+}
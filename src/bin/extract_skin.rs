@@ -0,0 +1,50 @@
+//! Turn a `def_pickel_001_col.nutexb` made for the 2 layer Steve model back into a skin png,
+//! which can be put in the plugin's skin folder.
+//!
+//! ```text
+//! extract_skin [--keep-colors] <def_pickel_001_col.nutexb> <skin.png>
+//! ```
+//!
+//! Color correction is undone if the texture looks color corrected, unless `--keep-colors`
+//! is passed.
+
+use std::fs;
+use std::process::exit;
+
+use minecraft_skins::error::Result;
+use minecraft_skins::skin_texture::extract_skin;
+
+const USAGE: &str = "Usage: extract_skin [--keep-colors] <def_pickel_001_col.nutexb> <skin.png>";
+
+fn run(nutexb_path: &str, png_path: &str, uncorrect: bool) -> Result<()> {
+    let skin = extract_skin(&fs::read(nutexb_path)?, uncorrect)?;
+
+    skin.save(png_path)?;
+
+    Ok(())
+}
+
+fn main() {
+    let mut uncorrect = true;
+    let mut paths = vec![];
+
+    for arg in std::env::args().skip(1) {
+        match &arg[..] {
+            "--keep-colors" => uncorrect = false,
+            _ => paths.push(arg),
+        }
+    }
+
+    let (nutexb_path, png_path) = match &paths[..] {
+        [nutexb_path, png_path] => (nutexb_path, png_path),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(1);
+        }
+    };
+
+    if let Err(err) = run(nutexb_path, png_path, uncorrect) {
+        eprintln!("Error: failed to extract {}: {}", nutexb_path, err);
+        exit(1);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::color_correct::ColorCorrection;
+
+static CHARA_3_MASK: &[u8] = include_bytes!("chara_3_mask.png");
+static CHARA_4_MASK: &[u8] = include_bytes!("chara_4_mask.png");
+static CHARA_6_MASK: &[u8] = include_bytes!("chara_6_mask.png");
+
+/// The character portraits generated from a render of the skin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharaImage {
+    Chara3,
+    Chara4,
+    Chara6,
+}
+
+impl CharaImage {
+    pub const ALL: [CharaImage; 3] = [CharaImage::Chara3, CharaImage::Chara4, CharaImage::Chara6];
+
+    /// Name of the ui folder the image goes in, such as `chara_3`
+    pub fn name(self) -> &'static str {
+        match self {
+            CharaImage::Chara3 => "chara_3",
+            CharaImage::Chara4 => "chara_4",
+            CharaImage::Chara6 => "chara_6",
+        }
+    }
+
+    fn mask(self) -> &'static [u8] {
+        match self {
+            CharaImage::Chara3 => CHARA_3_MASK,
+            CharaImage::Chara4 => CHARA_4_MASK,
+            CharaImage::Chara6 => CHARA_6_MASK,
+        }
+    }
+
+    /// Scale and x/y offset of the render within the image
+    fn placement(self) -> (f32, f32, f32) {
+        match self {
+            CharaImage::Chara3 => (1.28451252f32, -456.55612f32, 11.757321f32),
+            CharaImage::Chara4 => (0.232882008f32, -90.16959f32, 9.084564f32),
+            CharaImage::Chara6 => (0.938028f32, -480.87906f32, -96.13269f32),
+        }
+    }
+}
+
+/// Render a color corrected skin on Steve's model
+pub fn create_render(skin: &image::RgbaImage, colors: &ColorCorrection) -> image::RgbaImage {
+    let mut skin_data = skin.clone();
+
+    colors.apply(&mut skin_data);
+
+    minecraft_render::create_render(&skin_data)
+}
+
+pub fn create_chara_image(render: &image::RgbaImage, chara: CharaImage) -> image::RgbaImage {
+    let mask = image::load_from_memory_with_format(chara.mask(), image::ImageFormat::Png)
+        .unwrap()
+        .into_rgba8();
+
+    let (scale, x, y) = chara.placement();
+
+    minecraft_render::create_chara_image(render, &mask, scale, x, y)
+}
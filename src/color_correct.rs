@@ -1,15 +1,252 @@
+use std::fs;
+use std::path::PathBuf;
+
 use image::Pixel;
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// Name of the built in profile using the original curve
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Highest channel value the default profile can output
+const MAX_CORRECTED: u8 = 191;
+
+fn correct_channel(value: u8) -> u8 {
+    // gamma brightening by a factor of 1.385, bounded to [0, 184]
+    ((((value as f64) / 255.0).powf(1.0f64 / 1.200f64) * 255.0) * 191.0 / 255.0) as u8
+}
+
+fn uncorrect_channel(value: u8) -> u8 {
+    // the middle of the range of values that round down to this one
+    let corrected = ((value as f64) + 0.5) / (MAX_CORRECTED as f64);
+
+    (corrected.powf(1.200f64) * 255.0).round().min(255.0) as u8
+}
+
+/// A setting given either once for all color channels or separately for red, green and blue
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(untagged)]
+pub enum ChannelValues {
+    All(f64),
+    Each([f64; 3]),
+}
+
+impl ChannelValues {
+    fn get(&self, channel: usize) -> f64 {
+        match self {
+            ChannelValues::All(value) => *value,
+            ChannelValues::Each(values) => values[channel],
+        }
+    }
+}
+
+fn one() -> ChannelValues {
+    ChannelValues::All(1.0)
+}
+
+fn zero() -> ChannelValues {
+    ChannelValues::All(0.0)
+}
+
+/// How skin colors are adjusted to look right with the Steve model's material, set in the
+/// config's `color_profiles`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ColorProfile {
+    /// The original curve, a gamma of 1.2 scaled down to at most 191
+    Default,
+
+    /// `black_level + gain * value ^ (1 / gamma)`, with values from 0 to 1
+    Curve {
+        #[serde(default = "one")]
+        gamma: ChannelValues,
+        #[serde(default = "one")]
+        gain: ChannelValues,
+        #[serde(default = "zero")]
+        black_level: ChannelValues,
+    },
+
+    /// A lookup table loaded from a file, either 256 bytes used for every channel or 768 bytes
+    /// of red, then green, then blue
+    Lut {
+        path: PathBuf,
+    },
+
+    /// Adjustments made in linear light to the luminance of each color, scaling all channels
+    /// together so saturated colors keep their hue. The defaults match the original curve on
+    /// grays.
+    Linear {
+        /// Brightness change in stops
+        #[serde(default)]
+        exposure: f64,
+
+        /// Power applied to the luminance, below 1 brightens shadows
+        #[serde(default = "default_contrast")]
+        contrast: f64,
+
+        /// Linear luminance white is brought down to
+        #[serde(default = "default_white_level")]
+        white_level: f64,
+    },
+}
+
+pub fn default_contrast() -> f64 {
+    1.0 / 1.2
+}
+
+fn default_white_level() -> f64 {
+    srgb_to_linear(MAX_CORRECTED as f64 / 255.0)
+}
+
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        (1.055 * value.powf(1.0 / 2.4)) - 0.055
+    }
+}
+
+/// Relative luminance of a linear color
+pub fn luminance(color: [f64; 3]) -> f64 {
+    (0.2126 * color[0]) + (0.7152 * color[1]) + (0.0722 * color[2])
+}
+
+/// A color profile ready to be applied to skins
+#[derive(Debug, Clone)]
+pub enum ColorCorrection {
+    /// The output for every value of each color channel
+    Table(Box<[[u8; 256]; 3]>),
+
+    /// See `ColorProfile::Linear`
+    Linear {
+        exposure_scale: f64,
+        contrast: f64,
+        white_level: f64,
+    },
+}
+
+impl ColorCorrection {
+    fn table(mut f: impl FnMut(usize, u8) -> u8) -> Self {
+        let mut channels = [[0; 256]; 3];
+        for (channel, table) in channels.iter_mut().enumerate() {
+            for (value, output) in table.iter_mut().enumerate() {
+                *output = f(channel, value as u8);
+            }
+        }
+
+        ColorCorrection::Table(Box::new(channels))
+    }
+
+    /// Adjust the colors of a skin, alpha is left alone
+    pub fn apply(&self, skin_data: &mut image::RgbaImage) {
+        match self {
+            ColorCorrection::Table(channels) => {
+                for pixel in skin_data.pixels_mut() {
+                    for (channel, value) in pixel.channels_mut()[..3].iter_mut().enumerate() {
+                        *value = channels[channel][*value as usize];
+                    }
+                }
+            }
+            &ColorCorrection::Linear { exposure_scale, contrast, white_level } => {
+                let mut to_linear = [0.0; 256];
+                for (value, linear) in to_linear.iter_mut().enumerate() {
+                    *linear = srgb_to_linear(value as f64 / 255.0);
+                }
+
+                for pixel in skin_data.pixels_mut() {
+                    let channels = pixel.channels_mut();
+
+                    let mut color = [0.0; 3];
+                    for (linear, &value) in color.iter_mut().zip(channels.iter()) {
+                        *linear = to_linear[value as usize] * exposure_scale;
+                    }
+
+                    let old_luminance = luminance(color);
+                    if old_luminance <= 0.0 {
+                        continue
+                    }
+
+                    let new_luminance = white_level * old_luminance.powf(contrast);
+                    let mut scale = new_luminance / old_luminance;
+
+                    // scale down instead of clipping channels to keep the hue
+                    let brightest = color.iter().cloned().fold(0.0, f64::max);
+                    if brightest * scale > 1.0 {
+                        scale = 1.0 / brightest;
+                    }
+
+                    for (value, linear) in channels.iter_mut().zip(color.iter()) {
+                        *value = (linear_to_srgb(linear * scale) * 255.0).round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        ColorCorrection::table(|_, value| correct_channel(value))
+    }
+}
+
+impl ColorProfile {
+    /// A linear profile with the default white level
+    pub fn linear(exposure: f64, contrast: f64) -> Self {
+        ColorProfile::Linear { exposure, contrast, white_level: default_white_level() }
+    }
+
+    pub fn correction(&self) -> Result<ColorCorrection> {
+        match self {
+            ColorProfile::Default => Ok(ColorCorrection::default()),
+            ColorProfile::Curve { gamma, gain, black_level } => Ok(ColorCorrection::table(|channel, value| {
+                let value = (value as f64) / 255.0;
+                let output = black_level.get(channel) + (gain.get(channel) * value.powf(1.0 / gamma.get(channel)));
+
+                (output * 255.0).round().clamp(0.0, 255.0) as u8
+            })),
+            ColorProfile::Lut { path } => {
+                let lut = fs::read(path)?;
+
+                match lut.len() {
+                    256 => Ok(ColorCorrection::table(|_, value| lut[value as usize])),
+                    768 => Ok(ColorCorrection::table(|channel, value| lut[(channel * 256) + value as usize])),
+                    size => Err(Error::InvalidLut(size)),
+                }
+            }
+            &ColorProfile::Linear { exposure, contrast, white_level } => Ok(ColorCorrection::Linear {
+                exposure_scale: 2f64.powf(exposure),
+                contrast,
+                white_level,
+            }),
+        }
+    }
+}
+
+/// Whether a texture could have been corrected with the default profile, as it never outputs
+/// values above `MAX_CORRECTED`
+pub fn is_color_corrected(skin_data: &image::RgbaImage) -> bool {
+    skin_data
+        .pixels()
+        .all(|pixel| pixel.channels()[..3].iter().all(|&channel| channel <= MAX_CORRECTED))
+}
 
-pub fn color_correct(skin_data: &mut image::RgbaImage) {
+/// Approximately reverse the default profile, precision lost to rounding can't be recovered
+pub fn undo_color_correct(skin_data: &mut image::RgbaImage) {
     for row in skin_data.rows_mut() {
         for pixel in row {
-            let channels = pixel.channels_mut();
-            // 0..3 - don't apply to alpha channel
-            for i in 0..3 {
-                let pixel = &mut channels[i];
-                
-                // gamma brightening by a factor of 1.385, bounded to [0, 184]
-                *pixel = ((((*pixel as f64) / 255.0).powf(1.0f64 / 1.200f64) * 255.0) * 191.0 / 255.0) as u8;
+            // don't apply to alpha channel
+            for channel in &mut pixel.channels_mut()[..3] {
+                *channel = uncorrect_channel(*channel);
             }
         }
     }
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::auto_exposure::AutoExposure;
+use crate::color_correct::{ColorCorrection, ColorProfile, DEFAULT_PROFILE};
+use crate::error::{Error, Result};
+use crate::skin_meta::SkinMeta;
+use crate::skin_provider::ProviderConfig;
+use crate::skin_texture::TextureConfig;
+use crate::stock_generation::StockIconConfig;
+
+/// Settings loaded from `config.json` in the skin folder, anything left out uses the default
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Where skins are downloaded from when adding a skin by username
+    pub provider: ProviderConfig,
+
+    /// How skins are turned into the texture given to the game
+    pub texture: TextureConfig,
+
+    /// How stock icons are made from skins
+    pub stock_icon: StockIconConfig,
+
+    /// Color profile used for skins that don't pick their own, `default` if not set
+    pub color_profile: Option<String>,
+
+    /// Color profiles skins can pick by name, lookup table paths are relative to the config
+    pub color_profiles: HashMap<String, ColorProfile>,
+
+    /// Pick color settings for skins that don't have a color profile from their brightness
+    pub auto_exposure: bool,
+}
+
+impl Config {
+    /// Load the config, falling back to the defaults if it's missing or invalid
+    pub fn load(path: &Path) -> Self {
+        let json = match fs::read(path) {
+            Ok(json) => json,
+            Err(_) => return Config::default(),
+        };
+
+        match serde_json::from_slice::<Config>(&json) {
+            Ok(mut config) => {
+                if let Some(dir) = path.parent() {
+                    for profile in config.color_profiles.values_mut() {
+                        if let ColorProfile::Lut { path } = profile {
+                            *path = dir.join(&path);
+                        }
+                    }
+                }
+
+                config
+            }
+            Err(err) => {
+                println!("[minecraft_skins] Error: invalid config, using defaults: {}", err);
+                Config::default()
+            }
+        }
+    }
+
+    /// Get the color correction for a skin, from the skin's own color profile if it has one
+    pub fn color_correction(&self, skin_profile: Option<&str>) -> Result<ColorCorrection> {
+        let name = skin_profile
+            .or(self.color_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE);
+
+        match self.color_profiles.get(name) {
+            Some(profile) => profile.correction(),
+            None if name == DEFAULT_PROFILE => Ok(ColorCorrection::default()),
+            None => Err(Error::UnknownColorProfile(name.to_owned())),
+        }
+    }
+
+    /// Get the color correction for a cached skin. Skins use their own color profile if they
+    /// have one, then their auto exposure settings if auto exposure is on, then the config's
+    /// color profile. If auto exposure is on and the skin hasn't been analyzed yet, it's
+    /// analyzed and the result saved to its metadata.
+    pub fn skin_color_correction(&self, skin_path: &Path, skin: &image::RgbaImage) -> Result<ColorCorrection> {
+        let mut meta = SkinMeta::load(skin_path)?;
+
+        if meta.color_profile.is_some() {
+            return self.color_correction(meta.color_profile.as_deref())
+        }
+
+        // settings saved while auto exposure was on are left alone but not used
+        if !self.auto_exposure {
+            return self.color_correction(None)
+        }
+
+        let auto_exposure = match meta.auto_exposure {
+            Some(auto_exposure) => auto_exposure,
+            None => {
+                let auto_exposure = AutoExposure::analyze(skin);
+                meta.auto_exposure = Some(auto_exposure);
+
+                if let Err(err) = meta.save(skin_path) {
+                    println!("[minecraft_skins] Error: failed to save auto exposure for {}: {}", skin_path.display(), err);
+                }
+
+                auto_exposure
+            }
+        };
+
+        auto_exposure.profile().correction()
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::skin_meta::SkinModel;
+
+/// Classic Steve's texture, also shown in the menu for picking no skin
+pub static STEVE_PNG: &[u8] = include_bytes!("popup/steve.png");
+
+/// The only default skin bundled with the plugin
+const STEVE: DefaultSkin = DefaultSkin { name: "steve", model: SkinModel::Classic };
+
+const DEFAULT_SKIN_NAMES: [&str; 9] = [
+    "alex", "ari", "efe", "kai", "makena", "noor", "steve", "sunny", "zuri",
+];
+
+/// One of the skins Minecraft gives players who haven't set a skin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DefaultSkin {
+    pub name: &'static str,
+    pub model: SkinModel,
+}
+
+impl DefaultSkin {
+    /// Path of the texture in a folder laid out like the game's `entity/player` textures,
+    /// such as `slim/alex.png`
+    pub fn asset_path(&self) -> String {
+        let model = match self.model {
+            SkinModel::Slim => "slim",
+            SkinModel::Classic => "wide",
+        };
+
+        format!("{}/{}.png", model, self.name)
+    }
+
+    /// Get the texture for this skin along with its arm model, from `dir` (see `asset_path`).
+    /// Classic Steve is bundled, any other default skin not in `dir` is an error rather than
+    /// showing the wrong skin.
+    pub fn load_png(&self, dir: Option<&Path>) -> Result<(Vec<u8>, SkinModel)> {
+        match dir.map(|dir| fs::read(dir.join(self.asset_path()))) {
+            Some(Ok(png)) => Ok((png, self.model)),
+            _ if *self == STEVE => Ok((STEVE_PNG.to_vec(), SkinModel::Classic)),
+            _ => Err(Error::MissingDefaultSkin(self.asset_path())),
+        }
+    }
+}
+
+/// Java's `UUID.hashCode`, for a UUID given as 32 hex digits with or without dashes
+fn java_uuid_hash(uuid: &str) -> Option<i32> {
+    let hex: String = uuid.chars().filter(|&c| c != '-').collect();
+    if hex.len() != 32 {
+        return None
+    }
+
+    let uuid = u128::from_str_radix(&hex, 16).ok()?;
+    let hilo = ((uuid >> 64) as u64) ^ (uuid as u64);
+
+    Some(((hilo >> 32) as i32) ^ (hilo as i32))
+}
+
+/// Pick the default skin for a player the same way the game does: the UUID's hash indexes
+/// into the 9 default skins in slim then the 9 in classic
+pub fn default_skin_for_uuid(uuid: &str) -> Option<DefaultSkin> {
+    let index = java_uuid_hash(uuid)?.rem_euclid(DEFAULT_SKIN_NAMES.len() as i32 * 2) as usize;
+
+    let model = if index < DEFAULT_SKIN_NAMES.len() {
+        SkinModel::Slim
+    } else {
+        SkinModel::Classic
+    };
+
+    Some(DefaultSkin {
+        name: DEFAULT_SKIN_NAMES[index % DEFAULT_SKIN_NAMES.len()],
+        model,
+    })
+}
@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+
+use crate::skin_layout::face_mask;
+
+/// Fill every pixel not marked in `known` with the channels in `channels` of the closest known
+/// pixel, spreading out from the known pixels one pixel at a time
+fn dilate(image: &mut image::RgbaImage, known: &mut [bool], channels: std::ops::Range<usize>) {
+    let (width, height) = image.dimensions();
+
+    let mut queue: VecDeque<(u32, u32)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| known[(y * width + x) as usize])
+        .collect();
+
+    while let Some((x, y)) = queue.pop_front() {
+        let pixel = *image.get_pixel(x, y);
+
+        let neighbors = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+
+        for &(nx, ny) in &neighbors {
+            if nx >= width || ny >= height || known[(ny * width + nx) as usize] {
+                continue
+            }
+
+            let neighbor = image.get_pixel_mut(nx, ny);
+            neighbor.0[channels.clone()].copy_from_slice(&pixel.0[channels.clone()]);
+
+            known[(ny * width + nx) as usize] = true;
+            queue.push_back((nx, ny));
+        }
+    }
+}
+
+/// Extend the edges of each face of a modern layout skin out into the unused space around
+/// it, so texture filtering at the model's UV seams doesn't pull in unrelated colors. The
+/// color of transparent pixels is also filled in from the nearest visible pixel so they don't
+/// darken the edges of the overlay.
+pub fn pad_edges(skin: &mut image::RgbaImage) {
+    let scale = skin.width() / 64;
+
+    let mut on_face = face_mask(scale);
+    dilate(skin, &mut on_face, 0..4);
+
+    let mut visible: Vec<bool> = skin.pixels().map(|pixel| pixel.0[3] != 0).collect();
+    dilate(skin, &mut visible, 0..3);
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while downloading a skin, reading skins and textures, or
+/// turning a skin into a game file
+#[derive(Debug)]
+pub enum Error {
+    /// A file couldn't be read, or the output didn't fit in the buffer given by ARCropolis
+    Io(io::Error),
+
+    /// The skin isn't a valid image
+    Decode(image::ImageError),
+
+    /// The skin isn't a 64x64 or 64x32 layout (or a multiple of one) within the max size
+    InvalidSize { width: u32, height: u32 },
+
+    /// The generated file is larger than the space reserved for it
+    TooLarge { size: usize, max: usize },
+
+    /// Writing the nutexb or bntx failed
+    Encode(String),
+
+    /// A request to download a skin couldn't be made
+    Http(String),
+
+    /// The skin server responded with an error, such as 429 when rate limited
+    Status(i32),
+
+    /// The skin server doesn't know of a player with the given name
+    PlayerNotFound,
+
+    /// The player uses a default skin that isn't bundled or in the default skins folder
+    MissingDefaultSkin(String),
+
+    /// The skin server responded with a profile that couldn't be understood
+    InvalidProfile(&'static str),
+
+    /// A nutexb being read is malformed
+    InvalidNutexb(&'static str),
+
+    /// A nutexb being read uses a texture format that can't be decoded
+    UnsupportedFormat(u8),
+
+    /// A skin's json file isn't valid
+    InvalidMeta(String),
+
+    /// A skin or the config picks a color profile that isn't in the config
+    UnknownColorProfile(String),
+
+    /// A color profile's lookup table isn't 256 or 768 bytes
+    InvalidLut(usize),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn encode<E: fmt::Debug>(err: E) -> Self {
+        Error::Encode(format!("{:?}", err))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Decode(err) => write!(f, "failed to decode skin: {}", err),
+            Error::InvalidSize { width, height } => write!(f, "invalid skin size {}x{}", width, height),
+            Error::TooLarge { size, max } => write!(f, "file is 0x{:x} bytes, max is 0x{:x}", size, max),
+            Error::Encode(err) => write!(f, "failed to encode: {}", err),
+            Error::Http(err) => write!(f, "request failed: {}", err),
+            Error::Status(status) => write!(f, "skin server responded with status {}", status),
+            Error::PlayerNotFound => write!(f, "player not found"),
+            Error::MissingDefaultSkin(path) => write!(f, "default skin {} isn't in the default skins folder", path),
+            Error::InvalidProfile(reason) => write!(f, "invalid player profile: {}", reason),
+            Error::InvalidNutexb(reason) => write!(f, "invalid nutexb: {}", reason),
+            Error::UnsupportedFormat(format) => write!(f, "unsupported nutexb format 0x{:02x}", format),
+            Error::InvalidMeta(err) => write!(f, "invalid skin json: {}", err),
+            Error::UnknownColorProfile(name) => write!(f, "unknown color profile {:?}", name),
+            Error::InvalidLut(size) => write!(f, "color lookup table is {} bytes, expected 256 or 768", size),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Error::Decode(err)
+    }
+}
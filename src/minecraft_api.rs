@@ -1,5 +1,7 @@
 use serde::Deserialize;
 
+use crate::skin_meta::SkinModel;
+
 #[derive(Deserialize)]
 pub struct NameId {
     pub name: String,
@@ -34,8 +36,9 @@ pub struct Textures {
 
 #[derive(Deserialize)]
 pub struct TexturesInner {
+    /// Missing for players using one of the default skins
     #[serde(rename = "SKIN")]
-    pub skin: TextureSkin,
+    pub skin: Option<TextureSkin>,
 }
 
 #[derive(Deserialize)]
@@ -48,3 +51,12 @@ pub struct TextureSkin {
 pub struct SkinMetadata {
     pub model: String,
 }
+
+impl TextureSkin {
+    pub fn model(&self) -> SkinModel {
+        match &self.metadata {
+            Some(SkinMetadata { model }) if model == "slim" => SkinModel::Slim,
+            _ => SkinModel::Classic,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::skin_layout::face_ids;
+
+/// Halve an image, averaging each 2x2 box of pixels. Only pixels on the same face as most of
+/// the box are averaged so faces don't bleed into each other, and colors are weighted by alpha
+/// so transparent pixels don't darken the overlay.
+fn downsample(image: &image::RgbaImage, ids: &[u8]) -> (image::RgbaImage, Vec<u8>) {
+    let (width, height) = image.dimensions();
+    let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+
+    let mut half = image::RgbaImage::new(half_width, half_height);
+    let mut half_ids = vec![0; (half_width * half_height) as usize];
+
+    for y in 0..half_height {
+        for x in 0..half_width {
+            let mut box_pixels = Vec::with_capacity(4);
+            for &(dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let (px, py) = (((x * 2) + dx).min(width - 1), ((y * 2) + dy).min(height - 1));
+
+                box_pixels.push((ids[(py * width + px) as usize], *image.get_pixel(px, py)));
+            }
+
+            let id = box_pixels
+                .iter()
+                .map(|&(id, _)| id)
+                .filter(|&id| id != 0)
+                .max_by_key(|&id| box_pixels.iter().filter(|&&(other, _)| other == id).count())
+                .unwrap_or(0);
+
+            let mut color = [0u32; 3];
+            let mut alpha = 0u32;
+            let mut count = 0u32;
+            for (_, pixel) in box_pixels.iter().filter(|&&(other, _)| other == id) {
+                let [r, g, b, a] = pixel.0;
+                // weight by alpha, plus one so fully transparent boxes still keep their color
+                let weight = a as u32 + 1;
+
+                color[0] += r as u32 * weight;
+                color[1] += g as u32 * weight;
+                color[2] += b as u32 * weight;
+                alpha += a as u32;
+                count += 1;
+            }
+
+            let total_weight = alpha + count;
+            half.put_pixel(x, y, image::Rgba([
+                (color[0] / total_weight) as u8,
+                (color[1] / total_weight) as u8,
+                (color[2] / total_weight) as u8,
+                (alpha / count) as u8,
+            ]));
+            half_ids[(y * half_width + x) as usize] = id;
+        }
+    }
+
+    (half, half_ids)
+}
+
+/// Generate the full mip chain of a padded modern layout skin, down to 1x1. The first level
+/// is the skin itself.
+pub fn generate_mipmaps(skin: image::RgbaImage) -> Vec<image::RgbaImage> {
+    let mut ids = face_ids(skin.width() / 64);
+    let mut mips = vec![skin];
+
+    loop {
+        let last = mips.last().unwrap();
+        if last.width() == 1 && last.height() == 1 {
+            break
+        }
+
+        let (half, half_ids) = downsample(last, &ids);
+        mips.push(half);
+        ids = half_ids;
+    }
+
+    mips
+}
@@ -1,4 +1,4 @@
-use image::GenericImage;
+use image::{GenericImage, GenericImageView};
 
 /// Copy from one area to another, then flip the resulting area
 fn copy_flipped(image: &mut image::RgbaImage, from_pos: (u32, u32), size: (u32, u32), to_pos: (u32, u32)) {
@@ -23,7 +23,32 @@ fn copy_rotated_right_flipped(image: &mut image::RgbaImage, from_pos: (u32, u32)
     image::imageops::flip_horizontal_in_place(&mut image.sub_image(to_x, to_y, width, height));
 }
 
-pub fn convert_to_modern_skin(skin_data: &image::RgbaImage) -> image::RgbaImage {
+/// Clear the hat of a legacy skin if it has no transparent pixels. Old skins often filled
+/// the unused hat with a solid color, so the Java client treats a hat without transparency as
+/// no hat at all. Like the Java client, the right half of the skin above the legs is checked
+/// for transparency, which includes the body and right arm below the hat.
+fn clear_opaque_legacy_hat(skin_data: &mut image::RgbaImage, scale: u32) {
+    let checked = skin_data.view(32 * scale, 0, 32 * scale, 32 * scale);
+
+    let has_transparency = checked.pixels().any(|(_, _, pixel)| pixel.0[3] < 128);
+    if has_transparency {
+        return
+    }
+
+    let mut hat = skin_data.sub_image(32 * scale, 0, 32 * scale, 16 * scale);
+
+    let (width, height) = hat.dimensions();
+    for y in 0..height {
+        for x in 0..width {
+            hat.get_pixel_mut(x, y).0[3] = 0;
+        }
+    }
+}
+
+/// Convert a texture in the legacy 64x32 layout (or a multiple of it) to the modern 64x64
+/// layout, with the left arm and leg mirrored from the right ones. Only the layout changes,
+/// see `convert_to_modern_skin` for skins.
+pub fn convert_legacy_layout(skin_data: &image::RgbaImage) -> image::RgbaImage {
     let scale = skin_data.width() / 64;
 
     let mut new_skin = image::RgbaImage::new(64 * scale, 64 * scale);
@@ -52,3 +77,85 @@ pub fn convert_to_modern_skin(skin_data: &image::RgbaImage) -> image::RgbaImage
 
     new_skin
 }
+
+/// Convert a legacy 64x32 skin (or a multiple of it) to the modern 64x64 layout, clearing the
+/// hat if it's opaque like the Java client does for legacy skins
+pub fn convert_to_modern_skin(skin_data: &image::RgbaImage) -> image::RgbaImage {
+    let mut new_skin = convert_legacy_layout(skin_data);
+
+    clear_opaque_legacy_hat(&mut new_skin, skin_data.width() / 64);
+
+    new_skin
+}
+
+/// Source column (in skin pixels) for each column of a 4 pixel wide classic arm face,
+/// the middle column of the 3 pixel wide slim face is doubled up
+const SLIM_TO_CLASSIC_COLUMNS: [u32; 4] = [0, 1, 1, 2];
+
+/// Faces of an arm as (slim x, slim width, classic x, y, height) relative to the
+/// top-left of the arm's 16x16 area in the modern skin layout
+const ARM_FACES: [(u32, u32, u32, u32, u32); 6] = [
+    // top
+    (4, 3, 4, 0, 4),
+    // bottom
+    (7, 3, 8, 0, 4),
+    // outer side
+    (0, 4, 0, 4, 12),
+    // front
+    (4, 3, 4, 4, 12),
+    // inner side
+    (7, 4, 8, 4, 12),
+    // back
+    (11, 3, 12, 4, 12),
+];
+
+/// Top-left corners of the right arm, right sleeve, left arm and left sleeve
+const ARM_AREAS: [(u32, u32); 4] = [(40, 16), (40, 32), (32, 48), (48, 48)];
+
+/// Remap the 3 pixel wide arms of a slim (Alex) skin to the 4 pixel wide arms of a
+/// classic (Steve) skin, as the Steve model only has classic arms
+pub fn convert_slim_to_classic(skin_data: &mut image::RgbaImage) {
+    let scale = skin_data.width() / 64;
+
+    for &(area_x, area_y) in &ARM_AREAS {
+        let (area_x, area_y) = (area_x * scale, area_y * scale);
+        let arm = image::imageops::crop_imm(skin_data, area_x, area_y, 16 * scale, 16 * scale).to_image();
+
+        for &(slim_x, slim_width, classic_x, y, height) in &ARM_FACES {
+            for dst_x in 0..(4 * scale) {
+                let src_x = if slim_width == 4 {
+                    dst_x
+                } else {
+                    (SLIM_TO_CLASSIC_COLUMNS[(dst_x / scale) as usize] * scale) + (dst_x % scale)
+                };
+
+                for dy in 0..(height * scale) {
+                    let pixel = *arm.get_pixel((slim_x * scale) + src_x, (y * scale) + dy);
+                    skin_data.put_pixel(area_x + (classic_x * scale) + dst_x, area_y + (y * scale) + dy, pixel);
+                }
+            }
+        }
+    }
+}
+
+/// Areas of the base layer (head, body, arms and legs) as (x1, y1, x2, y2), the same areas
+/// the Java client makes opaque
+const BASE_LAYER_AREAS: [(u32, u32, u32, u32); 3] = [
+    (0, 0, 32, 16),
+    (0, 16, 64, 32),
+    (16, 48, 48, 64),
+];
+
+/// Make the base layer of a modern layout skin fully opaque, as Minecraft only draws
+/// transparency on the overlay
+pub fn make_base_layer_opaque(skin_data: &mut image::RgbaImage) {
+    let scale = skin_data.width() / 64;
+
+    for &(x1, y1, x2, y2) in &BASE_LAYER_AREAS {
+        for y in (y1 * scale)..(y2 * scale) {
+            for x in (x1 * scale)..(x2 * scale) {
+                skin_data.get_pixel_mut(x, y).0[3] = 255;
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::convert::TryInto;
+use std::io::Write;
+
+use serde::Deserialize;
+
+use crate::bcn;
+use crate::error::{Error, Result};
+use crate::swizzle;
+
+/// Size of the footer at the end of a nutexb with a single layer
+pub const FOOTER_SIZE: usize = 0xb0;
+
+pub const MAX_MIPS: usize = 16;
+
+pub const RGBA8_UNORM: u8 = 0x00;
+pub const RGBA8_SRGB: u8 = 0x05;
+pub const BGRA8_UNORM: u8 = 0x50;
+pub const BGRA8_SRGB: u8 = 0x55;
+pub const BC1_UNORM: u8 = 0x80;
+pub const BC1_SRGB: u8 = 0x85;
+pub const BC3_UNORM: u8 = 0xa0;
+pub const BC3_SRGB: u8 = 0xa5;
+pub const BC7_UNORM: u8 = 0xe0;
+pub const BC7_SRGB: u8 = 0xe5;
+
+/// Unknown byte after the format, 4 in the game's textures
+const FORMAT_UNK: u8 = 4;
+
+/// Format of the image data in a nutexb written by this crate
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureFormat {
+    /// Uncompressed, 4 bytes per pixel
+    Rgba8,
+
+    /// Half a byte per pixel, with pixels either opaque or fully transparent
+    Bc1,
+
+    /// A byte per pixel with smooth alpha
+    Bc3,
+
+    /// A byte per pixel, the best quality of the compressed formats
+    Bc7,
+}
+
+impl Default for TextureFormat {
+    fn default() -> Self {
+        TextureFormat::Rgba8
+    }
+}
+
+impl TextureFormat {
+    pub fn nutexb_format(self) -> u8 {
+        match self {
+            TextureFormat::Rgba8 => RGBA8_SRGB,
+            TextureFormat::Bc1 => BC1_SRGB,
+            TextureFormat::Bc3 => BC3_SRGB,
+            TextureFormat::Bc7 => BC7_SRGB,
+        }
+    }
+
+    /// Width and height in pixels of each block of the format
+    fn block_size(self) -> u32 {
+        match self {
+            TextureFormat::Rgba8 => 1,
+            _ => 4,
+        }
+    }
+
+    fn bytes_per_block(self) -> usize {
+        match self {
+            TextureFormat::Rgba8 => 4,
+            TextureFormat::Bc1 => 8,
+            TextureFormat::Bc3 | TextureFormat::Bc7 => 16,
+        }
+    }
+
+    /// Size of an image in blocks
+    fn size_in_blocks(self, width: u32, height: u32) -> (usize, usize) {
+        let block_size = self.block_size();
+
+        (((width + block_size - 1) / block_size) as usize, ((height + block_size - 1) / block_size) as usize)
+    }
+
+    /// Encode an image into rows of blocks
+    fn encode(self, image: &image::RgbaImage) -> Vec<u8> {
+        match self {
+            TextureFormat::Rgba8 => image.as_raw().clone(),
+            TextureFormat::Bc1 => bcn::compress_bc1(image),
+            TextureFormat::Bc3 => bcn::compress_bc3(image),
+            TextureFormat::Bc7 => bcn::compress_bc7(image),
+        }
+    }
+
+    /// Decode rows of blocks into RGBA pixels
+    fn decode(self, data: Vec<u8>, width: u32, height: u32) -> Vec<u8> {
+        match self {
+            TextureFormat::Rgba8 => data,
+            TextureFormat::Bc1 => bcn::decompress_bc1(&data, width, height),
+            TextureFormat::Bc3 => bcn::decompress_bc3(&data, width, height),
+            TextureFormat::Bc7 => bcn::decompress_bc7(&data, width, height),
+        }
+    }
+}
+
+/// Everything in the footer of a nutexb needed to find and decode the image data
+#[derive(Debug, Clone)]
+pub struct NutexbFooter {
+    pub mip_sizes: Vec<u32>,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub format: u8,
+    pub unk: u8,
+    pub mip_count: u32,
+    pub layer_count: u32,
+    pub data_size: u32,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// How the image data of a nutexb in `format` is stored, for the formats that can be read.
+/// BGRA is read as RGBA with the red and blue channels swapped afterwards.
+fn texture_format(format: u8) -> Option<TextureFormat> {
+    match format {
+        RGBA8_UNORM | RGBA8_SRGB | BGRA8_UNORM | BGRA8_SRGB => Some(TextureFormat::Rgba8),
+        BC1_UNORM | BC1_SRGB => Some(TextureFormat::Bc1),
+        BC3_UNORM | BC3_SRGB => Some(TextureFormat::Bc3),
+        BC7_UNORM | BC7_SRGB => Some(TextureFormat::Bc7),
+        _ => None,
+    }
+}
+
+impl NutexbFooter {
+    pub fn read(file: &[u8]) -> Result<Self> {
+        if file.len() < FOOTER_SIZE {
+            return Err(Error::InvalidNutexb("file is smaller than the footer"));
+        }
+
+        let footer = &file[file.len() - FOOTER_SIZE..];
+
+        if &footer[0x40..0x44] != b" XNT" || &footer[0xa8..0xac] != b" XET" {
+            return Err(Error::InvalidNutexb("missing nutexb magic"));
+        }
+
+        let name = &footer[0x44..0x84];
+        let name_len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+
+        let mip_count = read_u32(footer, 0x98);
+        if mip_count as usize > MAX_MIPS {
+            return Err(Error::InvalidNutexb("too many mipmaps"));
+        }
+
+        Ok(NutexbFooter {
+            mip_sizes: (0..mip_count as usize).map(|i| read_u32(footer, i * 4)).collect(),
+            name: String::from_utf8_lossy(&name[..name_len]).into_owned(),
+            width: read_u32(footer, 0x84),
+            height: read_u32(footer, 0x88),
+            depth: read_u32(footer, 0x8c),
+            format: footer[0x90],
+            unk: footer[0x91],
+            mip_count,
+            layer_count: read_u32(footer, 0xa0),
+            data_size: read_u32(footer, 0xa4),
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.mip_sizes.len() > MAX_MIPS {
+            return Err(Error::InvalidNutexb("too many mipmaps"));
+        }
+
+        let mut footer = [0; FOOTER_SIZE];
+
+        for (i, &size) in self.mip_sizes.iter().enumerate() {
+            write_u32(&mut footer, i * 4, size);
+        }
+
+        footer[0x40..0x44].copy_from_slice(b" XNT");
+
+        let name = self.name.as_bytes();
+        let name_len = name.len().min(0x3f);
+        footer[0x44..0x44 + name_len].copy_from_slice(&name[..name_len]);
+
+        write_u32(&mut footer, 0x84, self.width);
+        write_u32(&mut footer, 0x88, self.height);
+        write_u32(&mut footer, 0x8c, self.depth);
+        footer[0x90] = self.format;
+        footer[0x91] = self.unk;
+        // 2D texture
+        write_u32(&mut footer, 0x94, 4);
+        write_u32(&mut footer, 0x98, self.mip_count);
+        write_u32(&mut footer, 0x9c, 0x1000);
+        write_u32(&mut footer, 0xa0, self.layer_count);
+        write_u32(&mut footer, 0xa4, self.data_size);
+
+        footer[0xa8..0xac].copy_from_slice(b" XET");
+        footer[0xac..0xae].copy_from_slice(&1u16.to_le_bytes());
+        footer[0xae..0xb0].copy_from_slice(&2u16.to_le_bytes());
+
+        writer.write_all(&footer)?;
+
+        Ok(())
+    }
+}
+
+/// Size of the image data of a nutexb with the given mip levels
+pub fn data_size(format: TextureFormat, width: u32, height: u32, mip_count: usize) -> usize {
+    let block_height = swizzle::block_height_mip0(format.size_in_blocks(width, height).1);
+
+    (0..mip_count)
+        .map(|mip| {
+            let (width, height) = format.size_in_blocks((width >> mip).max(1), (height >> mip).max(1));
+
+            swizzle::swizzled_size(width, height, format.bytes_per_block(), swizzle::mip_block_height(height, block_height))
+        })
+        .sum()
+}
+
+/// Write a swizzled nutexb, `mips` being each level from largest to smallest. Returns the
+/// size of the file.
+pub fn write_nutexb<W: Write>(name: &str, mips: &[image::RgbaImage], format: TextureFormat, writer: &mut W) -> Result<usize> {
+    let (width, height) = mips[0].dimensions();
+    let block_height = swizzle::block_height_mip0(format.size_in_blocks(width, height).1);
+
+    let mut mip_sizes = Vec::with_capacity(mips.len());
+    for mip in mips {
+        let (mip_width, mip_height) = format.size_in_blocks(mip.width(), mip.height());
+        let mip_block_height = swizzle::mip_block_height(mip_height, block_height);
+
+        let swizzled = swizzle::swizzle(&format.encode(mip), mip_width, mip_height, format.bytes_per_block(), mip_block_height);
+        writer.write_all(&swizzled)?;
+
+        mip_sizes.push(swizzled.len() as u32);
+    }
+
+    let data_size: u32 = mip_sizes.iter().sum();
+
+    NutexbFooter {
+        mip_count: mips.len() as u32,
+        mip_sizes,
+        name: name.to_owned(),
+        width,
+        height,
+        depth: 1,
+        format: format.nutexb_format(),
+        unk: FORMAT_UNK,
+        layer_count: 1,
+        data_size,
+    }.write(writer)?;
+
+    Ok(data_size as usize + FOOTER_SIZE)
+}
+
+/// Read the base level of an RGBA8, BGRA8, BC1, BC3 or BC7 nutexb as RGBA
+pub fn read_rgba_image(file: &[u8]) -> Result<image::RgbaImage> {
+    let footer = NutexbFooter::read(file)?;
+
+    let format = texture_format(footer.format)
+        .ok_or(Error::UnsupportedFormat(footer.format))?;
+
+    let (width, height) = format.size_in_blocks(footer.width, footer.height);
+    let block_height = swizzle::block_height_mip0(height);
+
+    let blocks = swizzle::deswizzle(&file[..file.len() - FOOTER_SIZE], width, height, format.bytes_per_block(), block_height)
+        .ok_or(Error::InvalidNutexb("image data is smaller than the size in the footer"))?;
+
+    let mut pixels = format.decode(blocks, footer.width, footer.height);
+
+    if let BGRA8_UNORM | BGRA8_SRGB = footer.format {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    image::RgbaImage::from_raw(footer.width, footer.height, pixels)
+        .ok_or(Error::InvalidNutexb("image data doesn't match the size in the footer"))
+}
@@ -0,0 +1,426 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use parking_lot::Mutex;
+
+use arcropolis_api::{arc_callback, load_original_file};
+
+use skyline::hooks::{
+    getRegionAddress,
+    Region,
+    InlineCtx
+};
+use smash::lib::lua_const::FIGHTER_KIND_PICKEL;
+
+use crate::skin_files::*;
+use crate::skin_menu;
+use crate::slot_assignment::SlotAssignments;
+use crate::selections::SavedSelections;
+use crate::error::{Error, Result};
+use crate::skin_texture::{
+    load_skin,
+    move_footer_to_end,
+    write_skin_nutexb,
+    write_bntx,
+    write_stock_icon,
+};
+
+#[cfg(feature = "renders")]
+use crate::chara_renders::{self, CharaImage};
+
+lazy_static::lazy_static! {
+    static ref SKINS: Mutex<skin_menu::Skins> = Mutex::new(
+        skin_menu::Skins::from_cache().unwrap_or_default()
+    );
+}
+
+const TWO_LAYER_MOD: &str = "sd:/ultimate/mods/minecraft_2_layer";
+
+// Default 13.0.1 offset
+static mut FIGHTER_SELECTED_OFFSET: usize = 0x66e120;
+
+static FIGHTER_SELECTED_SEARCH_CODE: &[u8] = &[
+    0x04, 0xdc, 0x45, 0x94,
+    0xe0, 0x03, 0x1c, 0x32,
+    0xe1, 0x03, 0x1a, 0x32,
+];
+
+static SELECTED_SKINS: [Mutex<Option<PathBuf>>; 8] = [
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+];
+
+static RENDERS: [Mutex<Option<image::RgbaImage>>; 8] = [
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+    parking_lot::const_mutex(None),
+];
+
+/// The costume each slot is standing in for, slots only differ from their costume when
+/// multiple players pick the same costume with different skins
+static COSTUMES: [AtomicUsize; 8] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(1),
+    AtomicUsize::new(2),
+    AtomicUsize::new(3),
+    AtomicUsize::new(4),
+    AtomicUsize::new(5),
+    AtomicUsize::new(6),
+    AtomicUsize::new(7),
+];
+
+static SLOT_ASSIGNMENTS: Mutex<SlotAssignments> = parking_lot::const_mutex(SlotAssignments::new());
+
+static LAST_SELECTED: AtomicUsize = AtomicUsize::new(0xFF);
+
+extern "C" {
+    #[link_name = "_ZN2nn5prepo10PlayReport3AddEPKcS3_"]
+    fn prepo_add_play_report(a: u64, b: u64, c: u64) -> u64;
+}
+
+#[skyline::hook(replace = prepo_add_play_report)]
+fn prepo_add_play_report_hook(a: u64, b: u64, c: u64) -> u64 {
+    LAST_SELECTED.store(0xFF, Ordering::SeqCst);
+    SLOT_ASSIGNMENTS.lock().clear();
+
+    original!()(a, b, c)
+}
+
+fn costume_for_slot(slot: usize) -> usize {
+    COSTUMES[slot].load(Ordering::SeqCst)
+}
+
+/// Load the unmodified file of the costume a slot is standing in for, if the slot has been
+/// moved away from its own costume
+fn load_original_costume_file(files: &[u64; 8], slot: usize, data: &mut [u8]) -> Option<usize> {
+    let costume = costume_for_slot(slot);
+
+    if costume != slot {
+        load_original_file(files[costume], data)
+    } else {
+        None
+    }
+}
+
+/// Log why a file couldn't be generated and load the unmodified file of the slot's costume instead
+fn fall_back_to_original(files: &[u64; 8], slot: usize, data: &mut [u8], err: Error) -> Option<usize> {
+    println!("[minecraft_skins] Error: {}. Falling back to the original file.", err);
+
+    load_original_file(files[costume_for_slot(slot)], data)
+}
+
+/// Like `fall_back_to_original` for the skin texture, with the original's footer moved to the
+/// end of the space reserved for it where the game expects it
+fn fall_back_to_original_nutexb(slot: usize, data: &mut [u8], err: Error) -> Option<usize> {
+    let real_size = fall_back_to_original(&STEVE_NUTEXB_FILES, slot, data, err)?;
+
+    match move_footer_to_end(data, real_size) {
+        Ok(()) => Some(data.len()),
+        Err(err) => {
+            println!("[minecraft_skins] Error: {}. Couldn't fall back to the original file.", err);
+            None
+        }
+    }
+}
+
+fn write_steve_nutexb(slot: usize, data: &mut [u8]) -> Result<usize> {
+    let skin_path = SELECTED_SKINS[slot].lock();
+    let skin_path: Option<&Path> = skin_path.as_deref();
+
+    if let Some(path) = skin_path {
+        let skin = load_skin(path)?;
+        let colors = skin_menu::CONFIG.skin_color_correction(path, &skin)?;
+
+        write_skin_nutexb(skin, &skin_menu::CONFIG.texture, &colors, data)
+    } else {
+        // load skin for arcrop, temp fix, TODO: change back to "return false" after arcrop works
+        let nutexb = fs::read(
+            Path::new(TWO_LAYER_MOD)
+                .join(STEVE_NUTEXB_FILES_STR[costume_for_slot(slot)])
+        )?;
+
+        use std::io::Write;
+
+        let file_size = data.len();
+        let mut writer = std::io::Cursor::new(data);
+        let real_size = nutexb.len();
+
+        writer.write_all(&nutexb)?;
+        move_footer_to_end(writer.into_inner(), real_size)?;
+
+        Ok(file_size)
+    }
+}
+
+#[arc_callback]
+fn steve_callback(hash: u64, data: &mut [u8]) -> Option<usize> {
+    let slot = STEVE_NUTEXB_FILES.iter().position(|&x| x == hash)?;
+
+    match write_steve_nutexb(slot, data) {
+        Ok(size) => Some(size),
+        Err(err) => fall_back_to_original_nutexb(slot, data, err),
+    }
+}
+
+#[arc_callback]
+fn steve_stock_callback(hash: u64, data: &mut [u8]) -> Option<usize> {
+    if let Some(slot) = STEVE_STOCK_ICONS.iter().position(|&x| x == hash) {
+        let skin_path = SELECTED_SKINS[slot].lock();
+        let skin_path: Option<&Path> = skin_path.as_deref();
+
+        let skin_path = match skin_path {
+            Some(path) => path,
+            None => return load_original_costume_file(&STEVE_STOCK_ICONS, slot, data),
+        };
+
+        match load_skin(skin_path).and_then(|skin| write_stock_icon(&skin, &skin_menu::CONFIG.stock_icon, data)) {
+            Ok(size) => Some(size),
+            Err(err) => fall_back_to_original(&STEVE_STOCK_ICONS, slot, data, err),
+        }
+    } else {
+        None
+    }
+}
+
+#[derive(Debug)]
+struct UnkPtr1 {
+    ptrs: [&'static u64; 7],
+}
+
+#[derive(Debug)]
+struct UnkPtr2 {
+    bunch_bytes: [u8; 0x20],
+    bunch_bytes2: [u8; 0x20]
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct FighterInfo {
+    unk_ptr1: &'static UnkPtr1,
+    unk_ptr2: &'static UnkPtr2,
+    unk1: [u8; 0x20],
+    unk2: [u8; 0x20],
+    unk3: [u8; 0x8],
+    fighter_id: u8,
+    unk4: [u8;0xB],
+    fighter_slot: u8,
+}
+
+#[skyline::hook(offset = FIGHTER_SELECTED_OFFSET, inline)]
+fn css_fighter_selected(ctx: &InlineCtx) {
+    let infos = unsafe { &mut *(ctx.registers[0].bindgen_union_field as *mut FighterInfo) };
+
+    let is_steve = *FIGHTER_KIND_PICKEL == infos.fighter_id as i32;
+
+    if is_steve {
+        let costume = infos.fighter_slot as usize;
+
+        // each player's panel on the CSS has its own info, so its address tells players apart
+        let player = infos as *const FighterInfo as usize;
+
+        // the first pick of a costume with a saved skin uses it straight away, picking Steve
+        // again from the same panel opens the menu to change it
+        let saved = SavedSelections::load(Path::new(skin_menu::CACHE_DIR)).skin_path(costume);
+        let repick = SLOT_ASSIGNMENTS.lock().has_player(player);
+
+        let path = match saved {
+            Some(saved) if !repick => Some(saved),
+            _ => SKINS.lock().get_skin_path(),
+        };
+
+        let slot = SLOT_ASSIGNMENTS.lock().assign(player, costume, path.as_deref());
+        infos.fighter_slot = slot as u8;
+        COSTUMES[slot].store(costume, Ordering::SeqCst);
+
+        *SELECTED_SKINS[slot].lock() = path.clone();
+
+        // only remember picks made on the costume's own slot, moved slots only last for the session
+        if slot == costume {
+            let mut saved = SavedSelections::load(Path::new(skin_menu::CACHE_DIR));
+            saved.set(slot, path.as_deref());
+            saved.save();
+        }
+
+        let mut render = RENDERS[slot].lock();
+
+        #[cfg(feature = "renders")] {
+            *render = path.as_deref().and_then(|path| match create_render(path) {
+                Ok(render) => Some(render),
+                Err(err) => {
+                    println!("[minecraft_skins] Error: {}. Skipping render.", err);
+                    None
+                }
+            });
+        }
+    }
+}
+
+const MAX_STOCK_ICON_SIZE: usize = 0x9c68;
+const MAX_CHARA_3_SIZE: usize = 0x727068;
+const MAX_CHARA_4_SIZE: usize = 0x2d068;
+const MAX_CHARA_6_SIZE: usize = 0x81068;
+
+#[cfg(feature = "renders")]
+use parking_lot::{MutexGuard, MappedMutexGuard};
+
+#[cfg(feature = "renders")]
+fn create_render(path: &Path) -> Result<image::RgbaImage> {
+    let skin = load_skin(path)?;
+    let colors = skin_menu::CONFIG.skin_color_correction(path, &skin)?;
+
+    Ok(chara_renders::create_render(&skin, &colors))
+}
+
+#[cfg(feature = "renders")]
+fn get_render<'a>(slot: usize) -> Option<MappedMutexGuard<'a, image::RgbaImage>> {
+    let mut lock = RENDERS[slot].lock();
+    if lock.is_none() {
+        // selections restored from the last boot don't get rendered until they're needed
+        let path = SELECTED_SKINS[slot].lock().clone()?;
+        match create_render(&path) {
+            Ok(render) => *lock = Some(render),
+            Err(err) => {
+                println!("[minecraft_skins] Error: {}. Skipping render.", err);
+                return None
+            }
+        }
+    }
+
+    Some(MutexGuard::map(lock, |x| x.as_mut().unwrap()))
+}
+
+/// Restore the skins picked for each slot the last time the game was running
+fn restore_selections() {
+    let saved = SavedSelections::load(Path::new(skin_menu::CACHE_DIR));
+
+    for (slot, selected) in SELECTED_SKINS.iter().enumerate() {
+        *selected.lock() = saved.skin_path(slot);
+    }
+}
+
+#[cfg(feature = "renders")] 
+#[arc_callback]
+fn chara_3_callback(hash: u64, data: &mut [u8]) -> Option<usize> {
+    if let Some(slot) = STEVE_CHARA_3.iter().position(|&x| x == hash) {
+        let output = match get_render(slot) {
+            Some(output) => output,
+            None => return load_original_costume_file(&STEVE_CHARA_3, slot, data),
+        };
+
+        let chara_3 = chara_renders::create_chara_image(&output, CharaImage::Chara3);
+
+        match write_bntx(chara_3, data) {
+            Ok(size) => Some(size),
+            Err(err) => fall_back_to_original(&STEVE_CHARA_3, slot, data, err),
+        }
+    } else {
+        load_original_file(hash, data)
+    }
+}
+
+#[cfg(feature = "renders")] 
+#[arc_callback]
+fn chara_4_callback(hash: u64, data: &mut [u8]) -> Option<usize> {
+    let slot = STEVE_CHARA_4.iter().position(|&x| x == hash)?;
+    let output = match get_render(slot) {
+        Some(output) => output,
+        None => return load_original_costume_file(&STEVE_CHARA_4, slot, data),
+    };
+
+    let chara_4 = chara_renders::create_chara_image(&output, CharaImage::Chara4);
+
+    match write_bntx(chara_4, data) {
+        Ok(size) => Some(size),
+        Err(err) => fall_back_to_original(&STEVE_CHARA_4, slot, data, err),
+    }
+}
+
+#[cfg(feature = "renders")] 
+#[arc_callback]
+fn chara_6_callback(hash: u64, data: &mut [u8]) -> Option<usize> {
+    let slot = STEVE_CHARA_6.iter().position(|&x| x == hash)?;
+    let output = match get_render(slot) {
+        Some(output) => output,
+        None => return load_original_costume_file(&STEVE_CHARA_6, slot, data),
+    };
+
+    let chara_6 = chara_renders::create_chara_image(&output, CharaImage::Chara6);
+
+    match write_bntx(chara_6, data) {
+        Ok(size) => Some(size),
+        Err(err) => fall_back_to_original(&STEVE_CHARA_6, slot, data, err),
+    }
+}
+
+fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn search_offsets() {
+    unsafe {
+        let text_ptr = getRegionAddress(Region::Text) as *const u8;
+        let text_size = (getRegionAddress(Region::Rodata) as usize) - (text_ptr as usize);
+        let text = std::slice::from_raw_parts(text_ptr, text_size);
+
+        if let Some(offset) = find_subsequence(text, FIGHTER_SELECTED_SEARCH_CODE) {
+            FIGHTER_SELECTED_OFFSET = offset;
+        } else {
+            println!("Error: no offset found for 'css_fighter_selected'. Defaulting to 13.0.1 offset. This likely won't work.");
+        }
+    }
+}
+
+/// Space to reserve for each skin texture, enough for the largest texture the config can
+/// produce and the 2 layer mod's own textures used by slots without a skin
+fn steve_nutexb_size() -> usize {
+    let two_layer_size = STEVE_NUTEXB_FILES_STR
+        .iter()
+        .filter_map(|file| fs::metadata(Path::new(TWO_LAYER_MOD).join(file)).ok())
+        .map(|metadata| metadata.len() as usize)
+        .max()
+        .unwrap_or(0);
+
+    skin_menu::CONFIG.texture.max_file_size().max(two_layer_size)
+}
+
+#[skyline::main(name = "minecraft_skins")]
+pub fn main() {
+    search_offsets();
+    restore_selections();
+    skyline::install_hooks!(prepo_add_play_report_hook, css_fighter_selected);
+
+    let steve_nutexb_size = steve_nutexb_size();
+    for &hash in &STEVE_NUTEXB_FILES {
+        steve_callback::install(hash, steve_nutexb_size);
+    }
+
+    for &hash in &STEVE_STOCK_ICONS {
+        steve_stock_callback::install(hash, MAX_STOCK_ICON_SIZE);
+    }
+
+    #[cfg(feature = "renders")] {
+        for &hash in &STEVE_CHARA_3 {
+            chara_3_callback::install(hash, MAX_CHARA_3_SIZE);
+        }
+
+        for &hash in &STEVE_CHARA_4 {
+            chara_4_callback::install(hash, MAX_CHARA_4_SIZE);
+        }
+
+        for &hash in &STEVE_CHARA_6 {
+            chara_6_callback::install(hash, MAX_CHARA_6_SIZE);
+        }
+    }
+}
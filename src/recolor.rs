@@ -0,0 +1,257 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::modern_skin::convert_to_modern_skin;
+use crate::skin_layout::{BodyPart, Layer, Region};
+use crate::skin_meta::SkinMeta;
+
+/// Part of a skin a recolor applies to. The parts include both layers, `overlay` is the overlay
+/// of every part.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecolorRegion {
+    Head,
+    Body,
+    Arms,
+    Legs,
+    Overlay,
+}
+
+impl RecolorRegion {
+    fn faces(&self) -> Vec<Region> {
+        let parts: &[BodyPart] = match self {
+            RecolorRegion::Head => &[BodyPart::Head],
+            RecolorRegion::Body => &[BodyPart::Body],
+            RecolorRegion::Arms => &[BodyPart::RightArm, BodyPart::LeftArm],
+            RecolorRegion::Legs => &[BodyPart::RightLeg, BodyPart::LeftLeg],
+            RecolorRegion::Overlay => {
+                return BodyPart::ALL.iter().flat_map(|part| part.faces(Layer::Overlay).to_vec()).collect()
+            }
+        };
+
+        parts
+            .iter()
+            .flat_map(|part| Layer::ALL.iter().flat_map(move |&layer| part.faces(layer).to_vec()))
+            .collect()
+    }
+}
+
+/// A color to swap for another in a palette recolor
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PaletteColor {
+    pub from: [u8; 3],
+    pub to: [u8; 3],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecolorOperation {
+    /// Rotate the hue of every pixel by a number of degrees
+    HueShift { degrees: f64 },
+
+    /// Replace each pixel of exactly one color with another
+    Palette { colors: Vec<PaletteColor> },
+}
+
+/// A change of color to some parts of a skin
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recolor {
+    pub regions: Vec<RecolorRegion>,
+
+    #[serde(flatten)]
+    pub operation: RecolorOperation,
+}
+
+fn rgb_to_hsv(rgb: [f64; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let range = max - r.min(g).min(b);
+
+    let hue = if range == 0.0 {
+        0.0
+    } else if max == r {
+        (g - b) / range
+    } else if max == g {
+        2.0 + (b - r) / range
+    } else {
+        4.0 + (r - g) / range
+    };
+
+    let saturation = if max == 0.0 { 0.0 } else { range / max };
+
+    [(hue * 60.0).rem_euclid(360.0), saturation, max]
+}
+
+fn hsv_to_rgb(hsv: [f64; 3]) -> [f64; 3] {
+    let [hue, saturation, value] = hsv;
+    let sector = hue / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((sector % 2.0) - 1.0).abs());
+
+    let [r, g, b] = match sector as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+
+    let min = value - chroma;
+    [r + min, g + min, b + min]
+}
+
+impl RecolorOperation {
+    fn apply(&self, pixel: &mut image::Rgba<u8>) {
+        match self {
+            RecolorOperation::HueShift { degrees } => {
+                let rgb = [pixel[0] as f64 / 255.0, pixel[1] as f64 / 255.0, pixel[2] as f64 / 255.0];
+                let [hue, saturation, value] = rgb_to_hsv(rgb);
+                let rgb = hsv_to_rgb([(hue + degrees).rem_euclid(360.0), saturation, value]);
+
+                for channel in 0..3 {
+                    pixel[channel] = (rgb[channel] * 255.0).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            RecolorOperation::Palette { colors } => {
+                if let Some(color) = colors.iter().find(|color| color.from == [pixel[0], pixel[1], pixel[2]]) {
+                    pixel[0] = color.to[0];
+                    pixel[1] = color.to[1];
+                    pixel[2] = color.to[2];
+                }
+            }
+        }
+    }
+}
+
+/// Whether each pixel of a skin `scale` times the size of a 64x64 skin is in any of the
+/// regions, in rows
+fn region_mask(regions: &[RecolorRegion], scale: u32) -> Vec<bool> {
+    let size = 64 * scale;
+
+    let mut in_region = vec![false; (size * size) as usize];
+    for face in regions.iter().flat_map(|region| region.faces()) {
+        let face = face.scaled(scale);
+
+        for y in face.y..face.y + face.height {
+            for x in face.x..face.x + face.width {
+                in_region[(y * size + x) as usize] = true;
+            }
+        }
+    }
+
+    in_region
+}
+
+impl Recolor {
+    /// Recolor the regions of a modern layout skin, pixels in more than one region are only
+    /// changed once
+    pub fn apply(&self, skin: &mut image::RgbaImage) {
+        let in_region = region_mask(&self.regions, skin.width() / 64);
+
+        for (pixel, in_region) in skin.pixels_mut().zip(in_region) {
+            if in_region {
+                self.operation.apply(pixel);
+            }
+        }
+    }
+}
+
+/// Folder next to the skins that variants are saved in, with a folder for each skin
+pub const VARIANTS_DIR: &str = "variants";
+
+/// Path of the png a variant of a skin is saved to, `variants/<skin>/<variant>.png` next to
+/// the skin so it can't overwrite a real skin
+pub fn variant_path(skin_path: &Path, variant: &str) -> PathBuf {
+    let stem = skin_path.file_stem().unwrap_or_default();
+
+    skin_path.with_file_name(VARIANTS_DIR).join(stem).join(format!("{}.png", variant))
+}
+
+/// Folder a skin is in, or for a variant the folder of the skin it was made from
+pub fn skin_folder(skin_path: &Path) -> &Path {
+    let parent = skin_path.parent().unwrap_or_else(|| Path::new(""));
+
+    match parent.parent() {
+        Some(variants) if variants.file_name() == Some(VARIANTS_DIR.as_ref()) => {
+            variants.parent().unwrap_or_else(|| Path::new(""))
+        }
+        _ => parent,
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Save each variant in a skin's metadata as a skin of its own in the variants folder, leaving
+/// the original untouched. Variants are only made again once the skin or its metadata changes.
+/// Returns the paths of the variants.
+pub fn save_variants(skin_path: &Path) -> Result<Vec<PathBuf>> {
+    let meta = SkinMeta::load(skin_path)?;
+    if meta.variants.is_empty() {
+        return Ok(vec![])
+    }
+
+    let source_modified = modified(skin_path).max(modified(&skin_path.with_extension("json")));
+
+    let mut skin = None;
+    let mut paths = vec![];
+    for (name, recolors) in &meta.variants {
+        let path = variant_path(skin_path, name);
+
+        let up_to_date = match (modified(&path), source_modified) {
+            (Some(variant_modified), Some(source_modified)) => variant_modified >= source_modified,
+            _ => false,
+        };
+
+        if !up_to_date {
+            if skin.is_none() {
+                skin = Some(load_modern_png(skin_path)?);
+            }
+
+            let mut variant = skin.clone().unwrap();
+            for recolor in recolors {
+                recolor.apply(&mut variant);
+            }
+
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+
+            variant.save(&path)?;
+
+            // brightness is analyzed again as the colors changed
+            let variant_meta = SkinMeta {
+                variants: Default::default(),
+                auto_exposure: None,
+                ..meta.clone()
+            };
+            variant_meta.save(&path)?;
+        }
+
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+/// Load a skin png converted to the modern layout, keeping the arms as they are
+fn load_modern_png(path: &Path) -> Result<image::RgbaImage> {
+    let skin = image::open(path)?.into_rgba8();
+
+    let (width, height) = skin.dimensions();
+    if width < 64 || width % 64 != 0 || (width != height && width != height * 2) {
+        return Err(Error::InvalidSize { width, height });
+    }
+
+    if width == height * 2 {
+        Ok(convert_to_modern_skin(&skin))
+    } else {
+        Ok(skin)
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+const SELECTIONS_FILE: &str = "selections.json";
+
+/// The skin last picked for each costume slot, saved in the skin folder so picks survive
+/// restarting the game. Skins are stored by their path within the skin folder, so variants
+/// keep their `variants/<skin>/` folder.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SavedSelections {
+    slots: [Option<String>; 8],
+
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl SavedSelections {
+    /// Load the selections saved in a skin folder, none are picked if there aren't any
+    pub fn load(dir: &Path) -> Self {
+        let selections: Option<Self> = fs::read(dir.join(SELECTIONS_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok());
+
+        Self { dir: dir.to_owned(), ..selections.unwrap_or_default() }
+    }
+
+    pub fn save(&self) -> Option<()> {
+        let json = serde_json::to_vec_pretty(self).ok()?;
+
+        fs::write(self.dir.join(SELECTIONS_FILE), json).ok()
+    }
+
+    /// Get the path of the skin saved for a slot, if it is still in the skin folder
+    pub fn skin_path(&self, slot: usize) -> Option<PathBuf> {
+        let path = self.dir.join(self.slots[slot].as_ref()?);
+
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, slot: usize, skin_path: Option<&Path>) {
+        self.slots[slot] = skin_path
+            .and_then(|path| path.strip_prefix(&self.dir).ok())
+            .map(|name| name.to_string_lossy().into_owned());
+    }
+}
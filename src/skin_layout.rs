@@ -0,0 +1,148 @@
+//! The UV layout of a modern 64x64 Minecraft skin.
+//!
+//! Each body part is a box unwrapped into 6 faces, with the overlay (hat, jacket, sleeves and
+//! pants) laid out the same way at a different spot. Everything here is in skin pixels of a
+//! 64x64 skin, multiply by `width / 64` for HD skins.
+
+/// A rectangle of the skin texture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// This region in a skin `scale` times the size of a 64x64 skin
+    pub fn scaled(&self, scale: u32) -> Region {
+        Region {
+            x: self.x * scale,
+            y: self.y * scale,
+            width: self.width * scale,
+            height: self.height * scale,
+        }
+    }
+}
+
+/// The base layer or the overlay drawn slightly outside of it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layer {
+    Base,
+    Overlay,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 2] = [Layer::Base, Layer::Overlay];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyPart {
+    Head,
+    Body,
+    RightArm,
+    LeftArm,
+    RightLeg,
+    LeftLeg,
+}
+
+impl BodyPart {
+    pub const ALL: [BodyPart; 6] = [
+        BodyPart::Head,
+        BodyPart::Body,
+        BodyPart::RightArm,
+        BodyPart::LeftArm,
+        BodyPart::RightLeg,
+        BodyPart::LeftLeg,
+    ];
+
+    /// Width, height and depth of the part's box
+    fn size(&self) -> (u32, u32, u32) {
+        match self {
+            BodyPart::Head => (8, 8, 8),
+            BodyPart::Body => (8, 12, 4),
+            _ => (4, 12, 4),
+        }
+    }
+
+    /// Top-left corner of the part's faces for a layer
+    fn origin(&self, layer: Layer) -> (u32, u32) {
+        match (self, layer) {
+            (BodyPart::Head, Layer::Base) => (0, 0),
+            (BodyPart::Head, Layer::Overlay) => (32, 0),
+            (BodyPart::Body, Layer::Base) => (16, 16),
+            (BodyPart::Body, Layer::Overlay) => (16, 32),
+            (BodyPart::RightArm, Layer::Base) => (40, 16),
+            (BodyPart::RightArm, Layer::Overlay) => (40, 32),
+            (BodyPart::LeftArm, Layer::Base) => (32, 48),
+            (BodyPart::LeftArm, Layer::Overlay) => (48, 48),
+            (BodyPart::RightLeg, Layer::Base) => (0, 16),
+            (BodyPart::RightLeg, Layer::Overlay) => (0, 32),
+            (BodyPart::LeftLeg, Layer::Base) => (16, 48),
+            (BodyPart::LeftLeg, Layer::Overlay) => (0, 48),
+        }
+    }
+
+    /// The top, bottom, right, front, left and back faces of the part for a layer
+    pub fn faces(&self, layer: Layer) -> [Region; 6] {
+        let (width, height, depth) = self.size();
+        let (x, y) = self.origin(layer);
+
+        let region = |x, y, width, height| Region { x, y, width, height };
+
+        [
+            region(x + depth, y, width, depth),
+            region(x + depth + width, y, width, depth),
+            region(x, y + depth, depth, height),
+            region(x + depth, y + depth, width, height),
+            region(x + depth + width, y + depth, depth, height),
+            region(x + (depth * 2) + width, y + depth, width, height),
+        ]
+    }
+}
+
+/// Every face of every part on both layers
+pub fn all_faces() -> impl Iterator<Item = Region> {
+    BodyPart::ALL
+        .iter()
+        .flat_map(|part| Layer::ALL.iter().flat_map(move |&layer| part.faces(layer).to_vec()))
+}
+
+/// Which face each pixel of a skin `scale` times the size of a 64x64 skin is on, in rows.
+/// Faces are numbered from 1 in the order of `all_faces`, pixels not on a face are 0.
+pub fn face_ids(scale: u32) -> Vec<u8> {
+    let size = 64 * scale;
+    let mut ids = vec![0; (size * size) as usize];
+
+    for (i, face) in all_faces().enumerate() {
+        let face = face.scaled(scale);
+
+        for y in face.y..face.y + face.height {
+            for x in face.x..face.x + face.width {
+                ids[(y * size + x) as usize] = i as u8 + 1;
+            }
+        }
+    }
+
+    ids
+}
+
+/// Whether each pixel of a skin `scale` times the size of a 64x64 skin is on a face, in rows
+pub fn face_mask(scale: u32) -> Vec<bool> {
+    face_ids(scale).into_iter().map(|id| id != 0).collect()
+}
+
+/// Make the overlay of a part fully transparent in a modern layout skin
+pub fn clear_overlay(skin: &mut image::RgbaImage, part: BodyPart) {
+    let scale = skin.width() / 64;
+
+    for face in part.faces(Layer::Overlay).iter() {
+        let face = face.scaled(scale);
+
+        for y in face.y..face.y + face.height {
+            for x in face.x..face.x + face.width {
+                skin.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+            }
+        }
+    }
+}
@@ -6,14 +6,26 @@ use skyline_web::Webpage;
 use ramhorns::{Template, Content};
 use percent_encoding::percent_decode_str;
 
-use crate::minecraft_api::*;
 use crate::keyboard::ShowKeyboardArg;
+use crate::skin_meta::SkinMeta;
+use crate::config::Config;
+use crate::skin_provider::FetchedSkin;
 use crate::modern_skin::convert_to_modern_skin;
+use crate::recolor::save_variants;
+use crate::default_skins::STEVE_PNG;
 
 const LOCALHOST: &str = "http://localhost/";
-const CACHE_DIR: &str = "sd:/atmosphere/contents/01006A800016E000/romfs/minecraft_skins";
+pub const CACHE_DIR: &str = "sd:/atmosphere/contents/01006A800016E000/romfs/minecraft_skins";
 
-static STEVE_PNG: &[u8] = include_bytes!("popup/steve.png");
+/// Folder within the cache dir the game's default skin textures can be put in, laid out like
+/// the game's `entity/player` folder (`slim/alex.png`, `wide/steve.png`, ...)
+const DEFAULT_SKINS_DIR: &str = "default_skins";
+
+const CONFIG_FILE: &str = "config.json";
+
+lazy_static::lazy_static! {
+    pub static ref CONFIG: Config = Config::load(&Path::new(CACHE_DIR).join(CONFIG_FILE));
+}
 
 #[derive(Default)]
 pub struct Skins {
@@ -50,6 +62,18 @@ impl Skins {
     pub fn from_cache() -> Option<Self> {
         let _ = fs::create_dir_all(CACHE_DIR);
 
+        // make any recolored variants first so they're listed with the rest of the skins
+        let mut variants = vec![];
+        for entry in fs::read_dir(CACHE_DIR).ok()? {
+            let path = Path::new(CACHE_DIR).join(entry.ok()?.path());
+            if path.is_file() && path.extension().map(|x| x == "png").unwrap_or(false) {
+                match save_variants(&path) {
+                    Ok(paths) => variants.extend(paths),
+                    Err(err) => println!("[minecraft_skins] Error: failed to make variants of {}: {}", path.display(), err),
+                }
+            }
+        }
+
         let mut skins = vec![];
         let mut skin_files = vec![];
         for entry in fs::read_dir(CACHE_DIR).ok()? {
@@ -62,6 +86,14 @@ impl Skins {
             }
         }
 
+        // variants are in folders of their own, named by their path in the skin folder
+        for path in variants {
+            if let Ok(name) = path.strip_prefix(CACHE_DIR) {
+                skins.push(name.to_string_lossy().into_owned());
+                skin_files.push(path);
+            }
+        }
+
         Some(Skins { skins, skin_files })
     }
 
@@ -144,36 +176,30 @@ impl Skins {
     }
 
     fn download_skin(&mut self, username: &str) -> Option<PathBuf> {
-        let url = format!("https://api.mojang.com/users/profiles/minecraft/{}", username);
-        let response: NameId = minreq::get(url)
-            .send()
-            .ok()?
-            .json()
-            .ok()?;
-
-        let url = format!("https://sessionserver.mojang.com/session/minecraft/profile/{}", response.id);
-        let response: Session = minreq::get(url)
-            .send()
-            .ok()?
-            .json()
-            .ok()?;
+        let provider = CONFIG.provider.provider(Some(Path::new(CACHE_DIR).join(DEFAULT_SKINS_DIR)));
 
-        let textures_b64 = response.properties.into_iter().find(|prop| prop.name == "textures")?;
-        let textures_json = base64::decode(textures_b64.value).ok()?;
-        let textures: Textures = serde_json::from_slice(&textures_json[..]).ok()?;
-
-        let url = textures.textures.skin.url;
-        let png = minreq::get(url)
-            .send()
-            .ok()?
-            .into_bytes();
+        let FetchedSkin { png, model } = match provider.fetch_skin(username) {
+            Ok(skin) => skin,
+            Err(err) => {
+                println!("[minecraft_skins] Error: failed to download skin for {}: {}", username, err);
+                return None
+            }
+        };
 
         let path = Path::new(CACHE_DIR).join(format!("{}.png", username));
         fs::write(&path, &png)
             .ok()?;
 
-        self.skins.push(format!("{}.png", username));
-        self.skin_files.push(path.clone());
+        if let Err(err) = SkinMeta::save_model(&path, model) {
+            println!("[minecraft_skins] Error: failed to save the model of {}: {}", username, err);
+            return None
+        }
+
+        // downloading a skin again updates it in place
+        if !self.skin_files.contains(&path) {
+            self.skins.push(format!("{}.png", username));
+            self.skin_files.push(path.clone());
+        }
 
         Some(path)
     }
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::armor::Armor;
+use crate::auto_exposure::AutoExposure;
+use crate::error::{Error, Result};
+use crate::recolor::Recolor;
+use crate::skin_layout::{clear_overlay, BodyPart};
+
+/// Arm model of a skin, slim skins (Alex) have 3 pixel wide arms
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SkinModel {
+    Classic,
+    Slim,
+}
+
+impl Default for SkinModel {
+    fn default() -> Self {
+        SkinModel::Classic
+    }
+}
+
+/// Which parts of the overlay are shown, the same as the Minecraft client's skin customization
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct OverlayParts {
+    pub hat: bool,
+    pub jacket: bool,
+    pub left_sleeve: bool,
+    pub right_sleeve: bool,
+    pub left_pants: bool,
+    pub right_pants: bool,
+}
+
+impl Default for OverlayParts {
+    fn default() -> Self {
+        OverlayParts {
+            hat: true,
+            jacket: true,
+            left_sleeve: true,
+            right_sleeve: true,
+            left_pants: true,
+            right_pants: true,
+        }
+    }
+}
+
+impl OverlayParts {
+    fn all_shown(&self) -> bool {
+        *self == OverlayParts::default()
+    }
+
+    /// Clear the overlay of each hidden part from a modern layout skin
+    pub fn hide_parts(&self, skin_data: &mut image::RgbaImage) {
+        let parts = [
+            (self.hat, BodyPart::Head),
+            (self.jacket, BodyPart::Body),
+            (self.left_sleeve, BodyPart::LeftArm),
+            (self.right_sleeve, BodyPart::RightArm),
+            (self.left_pants, BodyPart::LeftLeg),
+            (self.right_pants, BodyPart::RightLeg),
+        ];
+
+        for &(shown, part) in &parts {
+            if !shown {
+                clear_overlay(skin_data, part);
+            }
+        }
+    }
+}
+
+/// Info about a cached skin, stored next to the skin png as a json file of the same name
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SkinMeta {
+    #[serde(default)]
+    pub model: SkinModel,
+
+    /// Name of the color profile to use instead of the one set in the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_profile: Option<String>,
+
+    /// Color settings picked from the skin's brightness, saved the first time the skin is used
+    /// with auto exposure on so it always looks the same
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_exposure: Option<AutoExposure>,
+
+    #[serde(default, skip_serializing_if = "OverlayParts::all_shown")]
+    pub overlay: OverlayParts,
+
+    #[serde(default, skip_serializing_if = "Armor::is_empty")]
+    pub armor: Armor,
+
+    /// Recolored versions of the skin by name, each saved as a skin of its own
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variants: HashMap<String, Vec<Recolor>>,
+}
+
+fn meta_path(skin_path: &Path) -> PathBuf {
+    skin_path.with_extension("json")
+}
+
+impl SkinMeta {
+    /// Load the metadata for a skin, falling back to the defaults if there is none. A json file
+    /// that can't be parsed is an error so it isn't overwritten by the defaults.
+    pub fn load(skin_path: &Path) -> Result<Self> {
+        let json = match fs::read(meta_path(skin_path)) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(SkinMeta::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        serde_json::from_slice(&json)
+            .map_err(|err| Error::InvalidMeta(err.to_string()))
+    }
+
+    pub fn save(&self, skin_path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(Error::encode)?;
+
+        Ok(fs::write(meta_path(skin_path), json)?)
+    }
+
+    /// Save the arm model of a freshly downloaded skin, keeping everything else already set in
+    /// its json file
+    pub fn save_model(skin_path: &Path, model: SkinModel) -> Result<()> {
+        let meta = SkinMeta { model, ..SkinMeta::load(skin_path)? };
+
+        meta.save(skin_path)
+    }
+}
@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::default_skins::default_skin_for_uuid;
+use crate::error::{Error, Result};
+use crate::minecraft_api::*;
+use crate::skin_meta::SkinModel;
+
+pub const MOJANG_API_URL: &str = "https://api.mojang.com";
+pub const MOJANG_SESSION_URL: &str = "https://sessionserver.mojang.com";
+
+pub const ELY_BY_API_URL: &str = "https://authserver.ely.by/api";
+pub const ELY_BY_SESSION_URL: &str = "https://authserver.ely.by/api/authlib-injector/sessionserver";
+
+/// A skin png fetched for a player
+pub struct FetchedSkin {
+    pub png: Vec<u8>,
+    pub model: SkinModel,
+}
+
+/// Somewhere skins can be looked up by player name
+pub trait SkinProvider {
+    fn fetch_skin(&self, username: &str) -> Result<FetchedSkin>;
+}
+
+fn get(url: &str) -> Result<minreq::Response> {
+    let response = minreq::get(url)
+        .send()
+        .map_err(|err| Error::Http(err.to_string()))?;
+
+    match response.status_code {
+        200 => Ok(response),
+        // Mojang has used both for players that don't exist
+        204 | 404 => Err(Error::PlayerNotFound),
+        status => Err(Error::Status(status)),
+    }
+}
+
+fn get_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    let response = get(url)?;
+
+    serde_json::from_slice(response.as_bytes())
+        .map_err(|_| Error::InvalidProfile("response isn't the expected json"))
+}
+
+/// Mojang's API, or another server implementing it such as Ely.by. Looks up the player's
+/// UUID, then their profile, then downloads the skin from the url in the profile's textures.
+pub struct MojangApi {
+    pub api_url: String,
+    pub session_url: String,
+
+    /// Folder the game's default skins can be found in, for players without a custom skin
+    pub default_skins_dir: Option<PathBuf>,
+}
+
+impl MojangApi {
+    pub fn fetch_textures(&self, username: &str) -> Result<Textures> {
+        let url = format!("{}/users/profiles/minecraft/{}", self.api_url, username);
+        let response: NameId = get_json(&url)?;
+
+        let url = format!("{}/session/minecraft/profile/{}", self.session_url, response.id);
+        let response: Session = get_json(&url)?;
+
+        let textures_b64 = response.properties
+            .into_iter()
+            .find(|prop| prop.name == "textures")
+            .ok_or(Error::InvalidProfile("profile has no textures"))?;
+
+        let textures_json = base64::decode(textures_b64.value)
+            .map_err(|_| Error::InvalidProfile("textures aren't valid base64"))?;
+
+        serde_json::from_slice(&textures_json[..])
+            .map_err(|_| Error::InvalidProfile("textures aren't the expected json"))
+    }
+}
+
+impl SkinProvider for MojangApi {
+    fn fetch_skin(&self, username: &str) -> Result<FetchedSkin> {
+        let textures = self.fetch_textures(username)?;
+
+        match textures.textures.skin {
+            Some(skin) => {
+                let model = skin.model();
+                let png = get(&skin.url)?.into_bytes();
+
+                Ok(FetchedSkin { png, model })
+            }
+            None => {
+                let default = default_skin_for_uuid(&textures.profile_id)
+                    .ok_or(Error::InvalidProfile("profile id isn't a valid UUID"))?;
+                let (png, model) = default.load_png(self.default_skins_dir.as_deref())?;
+
+                Ok(FetchedSkin { png, model })
+            }
+        }
+    }
+}
+
+/// Skins downloaded straight from a url, with `{username}` replaced by the player name
+pub struct SkinUrl {
+    pub url: String,
+    pub model: SkinModel,
+}
+
+impl SkinProvider for SkinUrl {
+    fn fetch_skin(&self, username: &str) -> Result<FetchedSkin> {
+        let png = get(&self.url.replace("{username}", username))?.into_bytes();
+
+        Ok(FetchedSkin { png, model: self.model })
+    }
+}
+
+fn mojang_api_url() -> String {
+    MOJANG_API_URL.to_owned()
+}
+
+fn mojang_session_url() -> String {
+    MOJANG_SESSION_URL.to_owned()
+}
+
+fn ely_by_api_url() -> String {
+    ELY_BY_API_URL.to_owned()
+}
+
+fn ely_by_session_url() -> String {
+    ELY_BY_SESSION_URL.to_owned()
+}
+
+/// Which skin provider to use, as set in the config
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProviderConfig {
+    Mojang {
+        #[serde(default = "mojang_api_url")]
+        api_url: String,
+        #[serde(default = "mojang_session_url")]
+        session_url: String,
+    },
+    ElyBy {
+        #[serde(default = "ely_by_api_url")]
+        api_url: String,
+        #[serde(default = "ely_by_session_url")]
+        session_url: String,
+    },
+    Url {
+        url: String,
+        #[serde(default)]
+        model: SkinModel,
+    },
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        ProviderConfig::Mojang {
+            api_url: mojang_api_url(),
+            session_url: mojang_session_url(),
+        }
+    }
+}
+
+impl ProviderConfig {
+    pub fn provider(&self, default_skins_dir: Option<PathBuf>) -> Box<dyn SkinProvider> {
+        match self {
+            ProviderConfig::Mojang { api_url, session_url }
+            | ProviderConfig::ElyBy { api_url, session_url } => Box::new(MojangApi {
+                api_url: api_url.clone(),
+                session_url: session_url.clone(),
+                default_skins_dir,
+            }),
+            ProviderConfig::Url { url, model } => Box::new(SkinUrl {
+                url: url.clone(),
+                model: *model,
+            }),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::Path;
+
+use image::DynamicImage;
+use serde::Deserialize;
+
+use crate::armor::ARMOR_DIR;
+use crate::color_correct::{is_color_corrected, undo_color_correct, ColorCorrection};
+use crate::edge_padding::pad_edges;
+use crate::error::{Error, Result};
+use crate::mipmaps::generate_mipmaps;
+use crate::nutexb_file::{self, read_rgba_image, write_nutexb, TextureFormat, FOOTER_SIZE, MAX_MIPS};
+use crate::modern_skin::{convert_to_modern_skin, convert_slim_to_classic, make_base_layer_opaque};
+use crate::recolor::skin_folder;
+use crate::skin_meta::{SkinMeta, SkinModel};
+use crate::stock_generation::{gen_stock_image, StockIconConfig};
+use crate::upscale::{upscale, UpscaleFilter};
+
+pub const MAX_HEIGHT: usize = 1024;
+pub const MAX_WIDTH: usize = 1024;
+pub const MAX_DATA_SIZE: usize = MAX_HEIGHT * MAX_WIDTH * 4;
+pub const MAX_FILE_SIZE: usize = MAX_DATA_SIZE + 0xb0;
+
+/// How skins are turned into the texture given to the game
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TextureConfig {
+    /// Width the skin is upscaled to, up to `MAX_WIDTH`
+    pub size: u32,
+    pub upscale_filter: UpscaleFilter,
+
+    /// Generate mipmaps so the skin doesn't shimmer from a distance. They take an extra third
+    /// of space, so a 1024 wide uncompressed texture is shrunk to 512 to fit.
+    pub mipmaps: bool,
+
+    /// Format of the texture, compressed formats need less memory reserved for each slot
+    pub format: TextureFormat,
+}
+
+impl Default for TextureConfig {
+    fn default() -> Self {
+        TextureConfig {
+            size: 512,
+            upscale_filter: UpscaleFilter::default(),
+            mipmaps: true,
+            format: TextureFormat::default(),
+        }
+    }
+}
+
+impl TextureConfig {
+    /// Largest nutexb these settings produce, this is the space reserved for each slot's skin
+    pub fn max_file_size(&self) -> usize {
+        let mut width = 64;
+        while width * 2 <= self.size.min(MAX_WIDTH as u32) {
+            width *= 2;
+        }
+
+        let mip_count = if self.mipmaps {
+            (32 - width.leading_zeros()) as usize
+        } else {
+            1
+        };
+
+        (nutexb_file::data_size(self.format, width, width, mip_count) + FOOTER_SIZE).min(MAX_FILE_SIZE)
+    }
+}
+
+/// Decode a skin png, converted to the modern layout with classic arms and an opaque base layer
+pub fn decode_skin(png: &[u8], model: SkinModel) -> Result<image::RgbaImage> {
+    let mut skin = image::load_from_memory(png)?.into_rgba8();
+
+    let (width, height) = skin.dimensions();
+    let valid_width = width >= 64 && width % 64 == 0 && width as usize <= MAX_WIDTH;
+    if !valid_width || (width != height && width != height * 2) {
+        return Err(Error::InvalidSize { width, height });
+    }
+
+    if width == height * 2 {
+        skin = convert_to_modern_skin(&skin);
+    }
+
+    make_base_layer_opaque(&mut skin);
+
+    if model == SkinModel::Slim {
+        convert_slim_to_classic(&mut skin);
+    }
+
+    Ok(skin)
+}
+
+/// Load a skin from the cache, converted to the modern layout with classic arms and an
+/// opaque base layer. Overlay parts hidden in the skin's metadata are cleared and its armor is
+/// drawn over the overlay, with textures from the `armor` folder next to the skin (or the skin
+/// a variant was made from).
+pub fn load_skin(path: &Path) -> Result<image::RgbaImage> {
+    let meta = SkinMeta::load(path)?;
+
+    let mut skin = decode_skin(&fs::read(path)?, meta.model)?;
+    meta.overlay.hide_parts(&mut skin);
+
+    if !meta.armor.is_empty() {
+        let armor_dir = skin_folder(path).join(ARMOR_DIR);
+        meta.armor.composite(&mut skin, &armor_dir)?;
+    }
+
+    Ok(skin)
+}
+
+/// Move the 0xb0 byte nutexb footer of a file smaller than the buffer it's in to the end of
+/// the buffer, as the game expects the footer at the end of the file
+pub fn move_footer_to_end(data: &mut [u8], real_size: usize) -> Result<()> {
+    if !(FOOTER_SIZE..=data.len()).contains(&real_size) {
+        return Err(Error::TooLarge { size: real_size, max: data.len() });
+    }
+
+    let end_of_data = data.len() - FOOTER_SIZE;
+    data.copy_within(real_size - FOOTER_SIZE..real_size, end_of_data);
+
+    Ok(())
+}
+
+/// Color correct, pad and upscale a skin and encode it as a nutexb, returning the size of the
+/// file. Mipmaps are generated if enabled, dropping the largest levels until they fit in
+/// `config.max_file_size()`, otherwise only the base level is written.
+pub fn encode_skin_nutexb<W: Write>(mut skin_data: image::RgbaImage, config: &TextureConfig, colors: &ColorCorrection, writer: &mut W) -> Result<usize> {
+    colors.apply(&mut skin_data);
+    pad_edges(&mut skin_data);
+
+    let size = config.size.min(MAX_WIDTH as u32);
+    let skin_data = upscale(skin_data, size, config.upscale_filter);
+
+    if !config.mipmaps {
+        return write_nutexb("steve_minecraft???", &[skin_data], config.format, writer)
+    }
+
+    let mut mips = generate_mipmaps(skin_data);
+    mips.truncate(MAX_MIPS);
+
+    let max_data_size = config.max_file_size() - FOOTER_SIZE;
+    while mips.len() > 1 && nutexb_file::data_size(config.format, mips[0].width(), mips[0].height(), mips.len()) > max_data_size {
+        mips.remove(0);
+    }
+
+    write_nutexb("steve_minecraft???", &mips, config.format, writer)
+}
+
+/// Color correct, pad and upscale a skin and write it as a nutexb filling `data`, returning
+/// the size of the file
+pub fn write_skin_nutexb(skin_data: image::RgbaImage, config: &TextureConfig, colors: &ColorCorrection, data: &mut [u8]) -> Result<usize> {
+    let file_size = data.len();
+    let mut writer = std::io::Cursor::new(data);
+
+    let real_size = encode_skin_nutexb(skin_data, config, colors, &mut writer)?;
+
+    move_footer_to_end(writer.into_inner(), real_size)?;
+
+    Ok(file_size)
+}
+
+pub fn encode_bntx<W: Write + Seek>(image: image::RgbaImage, writer: &mut W) -> Result<()> {
+    bntx::BntxFile::from_image(DynamicImage::ImageRgba8(image), "steve")
+        .write(writer)
+        .map_err(Error::encode)
+}
+
+pub fn write_bntx(image: image::RgbaImage, data: &mut [u8]) -> Result<usize> {
+    let mut writer = std::io::Cursor::new(data);
+
+    encode_bntx(image, &mut writer)?;
+
+    Ok(writer.position() as usize)
+}
+
+pub fn write_stock_icon(skin: &image::RgbaImage, config: &StockIconConfig, data: &mut [u8]) -> Result<usize> {
+    write_bntx(gen_stock_image(skin, config), data)
+}
+
+/// Extract a skin from a nutexb made for the 2 layer Steve model. If `uncorrect` is set and
+/// the texture looks like it was color corrected, the color correction is undone.
+pub fn extract_skin(nutexb: &[u8], uncorrect: bool) -> Result<image::RgbaImage> {
+    let mut skin = read_rgba_image(nutexb)?;
+
+    let (width, height) = skin.dimensions();
+    if width != height || width < 64 || width % 64 != 0 {
+        return Err(Error::InvalidSize { width, height });
+    }
+
+    if uncorrect && is_color_corrected(&skin) {
+        undo_color_correct(&mut skin);
+    }
+
+    Ok(skin)
+}
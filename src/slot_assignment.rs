@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+/// A costume slot handed out for a costume and skin, with the players using it
+struct Assignment {
+    costume: usize,
+    skin: Option<PathBuf>,
+    players: Vec<usize>,
+}
+
+/// Which costume and skin each of the 8 costume slots has been handed out for, and to which
+/// players, since the last time the assignments were cleared. Players are identified by any
+/// number that stays the same for them while picking, such as their port.
+pub struct SlotAssignments {
+    slots: [Option<Assignment>; 8],
+}
+
+impl SlotAssignments {
+    pub const fn new() -> Self {
+        SlotAssignments {
+            slots: [None, None, None, None, None, None, None, None],
+        }
+    }
+
+    fn is_assigned_to(&self, slot: usize, costume: usize, skin: Option<&Path>) -> bool {
+        match &self.slots[slot] {
+            Some(assignment) => assignment.costume == costume && assignment.skin.as_deref() == skin,
+            None => false,
+        }
+    }
+
+    /// Whether a player has picked a slot since the assignments were last cleared
+    pub fn has_player(&self, player: usize) -> bool {
+        self.slots.iter().flatten().any(|assignment| assignment.players.contains(&player))
+    }
+
+    /// Give up the slot a player was using, freeing it if no other player is using it
+    pub fn release(&mut self, player: usize) {
+        for slot in self.slots.iter_mut() {
+            if let Some(assignment) = slot {
+                assignment.players.retain(|&x| x != player);
+
+                if assignment.players.is_empty() {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    /// Get the slot a player picking `costume` with `skin` should be moved to, so that
+    /// players picking the same costume with different skins don't overwrite each other.
+    /// Whatever slot the player picked before is released first.
+    ///
+    /// Prefers the costume's own slot, then a slot already showing the same skin, then
+    /// the first unused slot. If all 8 slots are in use the costume's own slot is reused.
+    pub fn assign(&mut self, player: usize, costume: usize, skin: Option<&Path>) -> usize {
+        self.release(player);
+
+        let slot = if self.slots[costume].is_none() || self.is_assigned_to(costume, costume, skin) {
+            costume
+        } else if let Some(slot) = (0..8).find(|&slot| self.is_assigned_to(slot, costume, skin)) {
+            slot
+        } else if let Some(slot) = self.slots.iter().position(Option::is_none) {
+            slot
+        } else {
+            costume
+        };
+
+        match &mut self.slots[slot] {
+            Some(assignment) if assignment.costume == costume && assignment.skin.as_deref() == skin => {
+                assignment.players.push(player);
+            }
+            assignment => {
+                *assignment = Some(Assignment {
+                    costume,
+                    skin: skin.map(Path::to_owned),
+                    players: vec![player],
+                });
+            }
+        }
+
+        slot
+    }
+
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot = None;
+        }
+    }
+}
@@ -2,6 +2,54 @@ use ordered_float::NotNan;
 use image::imageops::{overlay, resize, Nearest};
 use image::{GenericImageView, Pixel, ImageFormat};
 use color_thief::{get_palette, ColorFormat, Color};
+use serde::Deserialize;
+
+use crate::skin_layout::{BodyPart, Layer, Region};
+
+/// Size of the face in a stock icon and where it's drawn in the outline
+const FACE_SIZE: u32 = 40;
+const FACE_POSITION: u32 = 12;
+
+/// What is drawn inside the stock icon outline
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StockIconMode {
+    /// The front of the face
+    Face,
+
+    /// A small isometric head showing the top, front and right side
+    Head,
+}
+
+impl Default for StockIconMode {
+    fn default() -> Self {
+        StockIconMode::Face
+    }
+}
+
+/// How stock icons are made from skins
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StockIconConfig {
+    pub mode: StockIconMode,
+
+    /// Draw the hat layer over the face
+    pub hat: bool,
+
+    /// Pixels the hat sticks out past the face on each side, like the larger hat box in game.
+    /// Isometric heads always draw the hat as large as it is in game.
+    pub hat_offset: u32,
+}
+
+impl Default for StockIconConfig {
+    fn default() -> Self {
+        StockIconConfig {
+            mode: StockIconMode::Face,
+            hat: true,
+            hat_offset: 0,
+        }
+    }
+}
 
 fn color_distance(x: Color, y: Color) -> f32 {
     (
@@ -11,14 +59,120 @@ fn color_distance(x: Color, y: Color) -> f32 {
     ).sqrt()
 }
 
-pub fn gen_stock_image(img: &image::RgbaImage) -> image::RgbaImage {
+/// The front of the face with the hat over it, returning the image and how far the hat sticks
+/// out past the face
+fn flat_face(img: &image::RgbaImage, config: &StockIconConfig) -> (image::RgbaImage, u32) {
+    let pixel_scale = img.width() / 64;
+    let offset = if config.hat { config.hat_offset.min(FACE_POSITION) } else { 0 };
+    let size = FACE_SIZE + 2 * offset;
+
+    let base = img.view(8 * pixel_scale, 8 * pixel_scale, 8 * pixel_scale, 8 * pixel_scale);
+    let mut face = image::RgbaImage::new(size, size);
+    overlay(&mut face, &resize(&base, FACE_SIZE, FACE_SIZE, Nearest), offset, offset);
+
+    if config.hat {
+        let hat = img.view(40 * pixel_scale, 8 * pixel_scale, 8 * pixel_scale, 8 * pixel_scale);
+        overlay(&mut face, &resize(&hat, size, size, Nearest), 0, 0);
+    }
+
+    (face, offset)
+}
+
+/// Where a point on a cube 1 unit wide lands on screen relative to the cube's center, in a
+/// 2:1 isometric view from the front right. x goes towards the head's left, y down and z back.
+fn project(point: [f64; 3]) -> [f64; 2] {
+    let [x, y, z] = point;
+
+    [x - z, y - (x + z) / 2.0]
+}
+
+/// A face of a cube as drawn in the isometric head, with the corner at the start of the
+/// texture and the directions its texture's columns and rows go along the cube
+struct CubeFace {
+    texture: Region,
+    corner: [f64; 3],
+    columns: [f64; 3],
+    rows: [f64; 3],
+    shade: f64,
+}
+
+/// The faces of a head layer seen from the front right, with the top lit the most like the
+/// game's inventory lighting
+fn visible_faces(layer: Layer, scale: u32) -> [CubeFace; 3] {
+    let [top, _, right, front, _, _] = BodyPart::Head.faces(layer);
+
+    [
+        CubeFace { texture: top.scaled(scale), corner: [0.0, 0.0, 1.0], columns: [1.0, 0.0, 0.0], rows: [0.0, 0.0, -1.0], shade: 1.0 },
+        CubeFace { texture: front.scaled(scale), corner: [0.0, 0.0, 0.0], columns: [1.0, 0.0, 0.0], rows: [0.0, 1.0, 0.0], shade: 0.8 },
+        CubeFace { texture: right.scaled(scale), corner: [0.0, 0.0, 1.0], columns: [0.0, 0.0, -1.0], rows: [0.0, 1.0, 0.0], shade: 0.6 },
+    ]
+}
+
+/// Color of a cube face at an offset from the cube's center in cube widths, if the offset is on it
+fn sample_face(img: &image::RgbaImage, face: &CubeFace, offset: [f64; 2]) -> Option<image::Rgba<u8>> {
+    let corner = project(face.corner);
+    let [column_x, column_y] = project(face.columns);
+    let [row_x, row_y] = project(face.rows);
+    let [dx, dy] = [offset[0] - corner[0], offset[1] - corner[1]];
+
+    // solve for how far along the columns and rows the offset is
+    let determinant = column_x * row_y - row_x * column_y;
+    let u = (dx * row_y - row_x * dy) / determinant;
+    let v = (column_x * dy - dx * column_y) / determinant;
+
+    if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+        return None
+    }
+
+    let texture = face.texture;
+    let x = texture.x + (u * texture.width as f64) as u32;
+    let y = texture.y + (v * texture.height as f64) as u32;
+
+    let mut color = *img.get_pixel(x, y);
+    for channel in 0..3 {
+        color[channel] = (color[channel] as f64 * face.shade).round() as u8;
+    }
+
+    Some(color)
+}
+
+/// A small isometric head with the hat drawn over it as a larger cube, like the head in game
+fn isometric_head(img: &image::RgbaImage, hat: bool) -> image::RgbaImage {
+    let scale = img.width() / 64;
+    let center = FACE_SIZE as f64 / 2.0;
+
+    // the hat is 9 pixels wide in game, sized so it's as tall as the space for the face
+    let hat_size = FACE_SIZE as f64 / 2.0;
+    let head_size = hat_size * 8.0 / 9.0;
+
+    let mut layers = vec![(visible_faces(Layer::Base, scale), head_size)];
+    if hat {
+        layers.push((visible_faces(Layer::Overlay, scale), hat_size));
+    }
+
+    let mut head = image::RgbaImage::new(FACE_SIZE, FACE_SIZE);
+    for (x, y, pixel) in head.enumerate_pixels_mut() {
+        for (faces, size) in &layers {
+            let offset = [(x as f64 + 0.5 - center) / size, (y as f64 + 0.5 - center) / size];
+
+            if let Some(color) = faces.iter().find_map(|face| sample_face(img, face, offset)) {
+                pixel.blend(&color);
+            }
+        }
+    }
+
+    head
+}
+
+/// Make a stock icon from the head of a modern layout skin
+pub fn gen_stock_image(img: &image::RgbaImage, config: &StockIconConfig) -> image::RgbaImage {
     let (width, height) = img.dimensions();
     assert_eq!(width, height);
 
-    let pixel_scale = width / 64;
-    
-    let face = img.view(8 * pixel_scale, 8 * pixel_scale, 8 * pixel_scale, 8 * pixel_scale);
-    let mut face = resize(&face, 40, 40, Nearest);
+    let (mut face, offset) = match config.mode {
+        StockIconMode::Face => flat_face(img, config),
+        StockIconMode::Head => (isometric_head(img, config.hat), 0),
+    };
 
     let mut outline = image::load_from_memory_with_format(
         include_bytes!("stock_outline.png"),
@@ -27,7 +181,7 @@ pub fn gen_stock_image(img: &image::RgbaImage) -> image::RgbaImage {
 
     let buf = face.as_raw();
     let pallete = get_palette(buf, ColorFormat::Rgba, 10, 4).unwrap();
-    
+
     for pixel in face.pixels_mut() {
         let channels = pixel.channels_mut();
 
@@ -46,7 +200,7 @@ pub fn gen_stock_image(img: &image::RgbaImage) -> image::RgbaImage {
         channels[2] = closest.b;
     }
 
-    overlay(&mut outline, &face, 12, 12);
+    overlay(&mut outline, &face, FACE_POSITION - offset, FACE_POSITION - offset);
 
     outline
 }
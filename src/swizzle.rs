@@ -0,0 +1,111 @@
+//! Tegra X1 block linear swizzling, the memory layout the Switch uses for texture data.
+//!
+//! Textures are made of GOBs (64 bytes by 8 rows), stacked `block_height` GOBs tall into
+//! blocks, which are laid out left to right then top to bottom. Sizes are in blocks, which
+//! are pixels for uncompressed formats and 4x4 pixel tiles for block compressed formats.
+
+const GOB_WIDTH: usize = 64;
+const GOB_HEIGHT: usize = 8;
+const GOB_SIZE: usize = GOB_WIDTH * GOB_HEIGHT;
+
+fn div_round_up(x: usize, d: usize) -> usize {
+    (x + d - 1) / d
+}
+
+/// Block height (in GOBs) the game uses for the base level of a texture `height` blocks tall
+pub fn block_height_mip0(height: usize) -> usize {
+    let height_and_half = height + (height / 2);
+
+    if height_and_half >= 128 {
+        16
+    } else if height_and_half >= 64 {
+        8
+    } else if height_and_half >= 32 {
+        4
+    } else if height_and_half >= 16 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Block height (in GOBs) of a mipmap `mip_height` blocks tall, smaller levels shrink the
+/// block height so they aren't mostly padding
+pub fn mip_block_height(mip_height: usize, block_height_mip0: usize) -> usize {
+    let mut block_height = block_height_mip0;
+    while mip_height <= (block_height / 2) * GOB_HEIGHT && block_height > 1 {
+        block_height /= 2;
+    }
+
+    block_height
+}
+
+/// Size in bytes of a swizzled surface, including padding out to whole blocks
+pub fn swizzled_size(width: usize, height: usize, bytes_per_block: usize, block_height: usize) -> usize {
+    let width_in_gobs = div_round_up(width * bytes_per_block, GOB_WIDTH);
+    let height_in_blocks = div_round_up(height, GOB_HEIGHT * block_height);
+
+    width_in_gobs * height_in_blocks * GOB_SIZE * block_height
+}
+
+/// Offset of byte `x` of row `y` within a swizzled surface
+fn swizzled_offset(x: usize, y: usize, width_in_gobs: usize, block_height: usize) -> usize {
+    let block_size = GOB_SIZE * block_height;
+
+    let block_address = (y / (GOB_HEIGHT * block_height)) * block_size * width_in_gobs
+        + (x / GOB_WIDTH) * block_size
+        + ((y % (GOB_HEIGHT * block_height)) / GOB_HEIGHT) * GOB_SIZE;
+
+    let (x, y) = (x % GOB_WIDTH, y % GOB_HEIGHT);
+
+    block_address
+        + (x / 32) * 256
+        + (y / 2) * 64
+        + ((x % 32) / 16) * 32
+        + (y % 2) * 16
+        + (x % 16)
+}
+
+fn copy_swizzled(
+    width: usize,
+    height: usize,
+    bytes_per_block: usize,
+    block_height: usize,
+    mut copy: impl FnMut(usize, usize),
+) {
+    let row_size = width * bytes_per_block;
+    let width_in_gobs = div_round_up(row_size, GOB_WIDTH);
+
+    for y in 0..height {
+        for x in 0..row_size {
+            copy(swizzled_offset(x, y, width_in_gobs, block_height), (y * row_size) + x);
+        }
+    }
+}
+
+/// Swizzle tightly packed rows of blocks
+pub fn swizzle(linear: &[u8], width: usize, height: usize, bytes_per_block: usize, block_height: usize) -> Vec<u8> {
+    let mut swizzled = vec![0; swizzled_size(width, height, bytes_per_block, block_height)];
+
+    copy_swizzled(width, height, bytes_per_block, block_height, |swizzled_offset, linear_offset| {
+        swizzled[swizzled_offset] = linear[linear_offset];
+    });
+
+    swizzled
+}
+
+/// Deswizzle a surface into tightly packed rows of blocks, returns `None` if `swizzled` is
+/// too small for the given size
+pub fn deswizzle(swizzled: &[u8], width: usize, height: usize, bytes_per_block: usize, block_height: usize) -> Option<Vec<u8>> {
+    if swizzled.len() < swizzled_size(width, height, bytes_per_block, block_height) {
+        return None
+    }
+
+    let mut linear = vec![0; width * height * bytes_per_block];
+
+    copy_swizzled(width, height, bytes_per_block, block_height, |swizzled_offset, linear_offset| {
+        linear[linear_offset] = swizzled[swizzled_offset];
+    });
+
+    Some(linear)
+}
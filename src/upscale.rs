@@ -0,0 +1,102 @@
+use image::imageops::{resize, Nearest};
+use serde::Deserialize;
+
+use crate::skin_layout::face_ids;
+
+/// How a skin is scaled up before being given to the game. Upscaling keeps the game's
+/// texture filtering from blurring the edges of each skin pixel.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpscaleFilter {
+    /// Every skin pixel becomes a square block of pixels
+    Nearest,
+
+    /// Scale2x (EPX), rounds off diagonal staircases while keeping edges hard. Applied
+    /// repeatedly for larger scales, on each face on its own.
+    Scale2x,
+}
+
+impl Default for UpscaleFilter {
+    fn default() -> Self {
+        UpscaleFilter::Nearest
+    }
+}
+
+/// Scale2x on a modern layout skin, each pixel becomes 2x2 pixels taking the color of matching
+/// neighbors on corners. Only neighbors on the same face are used, so faces next to each other
+/// in the texture but not on the model don't change each other's edges.
+fn scale2x(image: &image::RgbaImage) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+    let mut scaled = image::RgbaImage::new(width * 2, height * 2);
+
+    let ids = face_ids(width / 64);
+    let id = |x: u32, y: u32| ids[(y * width + x) as usize];
+
+    // neighbors past the edge of the face are clamped to it, which is the center pixel
+    let neighbor = |x: u32, y: u32, dx: i64, dy: i64| {
+        let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+        if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 || id(nx as u32, ny as u32) != id(x, y) {
+            return *image.get_pixel(x, y)
+        }
+
+        *image.get_pixel(nx as u32, ny as u32)
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let center = *image.get_pixel(x, y);
+            let up = neighbor(x, y, 0, -1);
+            let down = neighbor(x, y, 0, 1);
+            let left = neighbor(x, y, -1, 0);
+            let right = neighbor(x, y, 1, 0);
+
+            let (mut top_left, mut top_right, mut bottom_left, mut bottom_right) = (center, center, center, center);
+            if up != down && left != right {
+                if up == left {
+                    top_left = up;
+                }
+                if up == right {
+                    top_right = up;
+                }
+                if down == left {
+                    bottom_left = down;
+                }
+                if down == right {
+                    bottom_right = down;
+                }
+            }
+
+            scaled.put_pixel(x * 2, y * 2, top_left);
+            scaled.put_pixel((x * 2) + 1, y * 2, top_right);
+            scaled.put_pixel(x * 2, (y * 2) + 1, bottom_left);
+            scaled.put_pixel((x * 2) + 1, (y * 2) + 1, bottom_right);
+        }
+    }
+
+    scaled
+}
+
+/// Upscale a modern layout skin to be at most `size` pixels wide. The skin is scaled by the
+/// largest power of two that fits so every skin pixel stays the same size, skins already at
+/// least `size` wide are left alone.
+pub fn upscale(skin: image::RgbaImage, size: u32, filter: UpscaleFilter) -> image::RgbaImage {
+    let (width, height) = skin.dimensions();
+
+    let mut scale = 1;
+    while width * scale * 2 <= size {
+        scale *= 2;
+    }
+
+    match filter {
+        _ if scale == 1 => skin,
+        UpscaleFilter::Nearest => resize(&skin, width * scale, height * scale, Nearest),
+        UpscaleFilter::Scale2x => {
+            let mut skin = skin;
+            while skin.width() < width * scale {
+                skin = scale2x(&skin);
+            }
+
+            skin
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Compares the original color curve with the linear light profile on reference colors
+
+use minecraft_skins::color_correct::{ColorCorrection, ColorProfile};
+
+/// A gray ramp followed by saturated colors, one pixel each
+fn reference_image() -> image::RgbaImage {
+    let mut colors: Vec<[u8; 4]> = (0..=255).step_by(15).map(|v| [v as u8, v as u8, v as u8, 255]).collect();
+    colors.extend_from_slice(&SATURATED_COLORS);
+
+    let mut image = image::RgbaImage::new(colors.len() as u32, 1);
+    for (pixel, color) in image.pixels_mut().zip(colors) {
+        *pixel = image::Rgba(color);
+    }
+
+    image
+}
+
+const SATURATED_COLORS: [[u8; 4]; 6] = [
+    [255, 0, 0, 255],
+    [255, 128, 0, 255],
+    [40, 200, 60, 255],
+    [0, 200, 255, 255],
+    [200, 30, 120, 255],
+    [90, 20, 160, 255],
+];
+
+fn corrected(correction: &ColorCorrection) -> image::RgbaImage {
+    let mut image = reference_image();
+    correction.apply(&mut image);
+
+    image
+}
+
+fn linear_profile(json: &str) -> ColorCorrection {
+    serde_json::from_str::<ColorProfile>(json).unwrap().correction().unwrap()
+}
+
+/// Hue in degrees
+fn hue(pixel: &image::Rgba<u8>) -> f64 {
+    let [r, g, b] = [pixel[0] as f64, pixel[1] as f64, pixel[2] as f64];
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let range = max - min;
+
+    let hue = if max == r {
+        (g - b) / range
+    } else if max == g {
+        2.0 + (b - r) / range
+    } else {
+        4.0 + (r - g) / range
+    };
+
+    (hue * 60.0).rem_euclid(360.0)
+}
+
+fn hue_shift(from: &image::Rgba<u8>, to: &image::Rgba<u8>) -> f64 {
+    let shift = (hue(from) - hue(to)).abs();
+
+    shift.min(360.0 - shift)
+}
+
+#[test]
+fn default_profile_matches_original_curve() {
+    let default = corrected(&ColorCorrection::default());
+
+    for (before, after) in reference_image().pixels().zip(default.pixels()) {
+        for channel in 0..3 {
+            let expected = ((((before[channel] as f64) / 255.0).powf(1.0 / 1.2) * 255.0) * 191.0 / 255.0) as u8;
+            assert_eq!(after[channel], expected);
+        }
+
+        assert_eq!(after[3], before[3]);
+    }
+
+    assert_eq!(default.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert_eq!(default.get_pixel(17, 0).0, [191, 191, 191, 255]);
+}
+
+#[test]
+fn linear_profile_is_close_to_default_on_grays() {
+    let default = corrected(&ColorCorrection::default());
+    let linear = corrected(&linear_profile(r#"{ "type": "linear" }"#));
+
+    for x in 0..18 {
+        let (default, linear) = (default.get_pixel(x, 0), linear.get_pixel(x, 0));
+
+        assert_eq!(linear[0], linear[1]);
+        assert_eq!(linear[1], linear[2]);
+        assert!((default[0] as i32 - linear[0] as i32).abs() <= 12, "{:?} vs {:?}", default, linear);
+    }
+}
+
+#[test]
+fn linear_profile_keeps_hue() {
+    let reference = reference_image();
+    let default = corrected(&ColorCorrection::default());
+    let linear = corrected(&linear_profile(r#"{ "type": "linear" }"#));
+
+    let mut total_default_shift = 0.0;
+    let mut total_linear_shift = 0.0;
+
+    for x in 18..reference.width() {
+        let original = reference.get_pixel(x, 0);
+        let linear_shift = hue_shift(original, linear.get_pixel(x, 0));
+
+        assert!(linear_shift < 2.0, "{:?} shifted {} degrees", original, linear_shift);
+
+        total_default_shift += hue_shift(original, default.get_pixel(x, 0));
+        total_linear_shift += linear_shift;
+    }
+
+    assert!(total_linear_shift < total_default_shift);
+}
+
+#[test]
+fn exposure_brightens() {
+    let normal = corrected(&linear_profile(r#"{ "type": "linear" }"#));
+    let brighter = corrected(&linear_profile(r#"{ "type": "linear", "exposure": 1.0 }"#));
+
+    for (normal, brighter) in normal.pixels().zip(brighter.pixels()).skip(1) {
+        assert!(brighter[0] >= normal[0] && brighter[1] >= normal[1] && brighter[2] >= normal[2]);
+    }
+
+    assert!(brighter.get_pixel(8, 0)[0] > normal.get_pixel(8, 0)[0]);
+}
@@ -0,0 +1,135 @@
+//! Converting Notch-era 64x32 skins, which often filled the unused hat with a solid color
+
+use minecraft_skins::modern_skin::convert_legacy_layout;
+use minecraft_skins::skin_meta::SkinModel;
+use minecraft_skins::skin_texture::decode_skin;
+
+const SKIN_COLOR: [u8; 4] = [198, 150, 128, 255];
+const SHIRT_COLOR: [u8; 4] = [0, 175, 175, 255];
+const PANTS_COLOR: [u8; 4] = [70, 60, 160, 255];
+const HAT_COLOR: [u8; 4] = [20, 20, 20, 255];
+
+/// A classic skin in the legacy layout, `scale` times 64x32, with the hat filled by `hat`
+fn legacy_skin(scale: u32, hat: impl Fn(u32, u32) -> [u8; 4]) -> Vec<u8> {
+    let mut skin = image::RgbaImage::new(64 * scale, 32 * scale);
+
+    for (x, y, pixel) in skin.enumerate_pixels_mut() {
+        let (skin_x, skin_y) = (x / scale, y / scale);
+
+        *pixel = image::Rgba(match (skin_x, skin_y) {
+            (0..=31, 0..=15) => SKIN_COLOR,
+            (32..=63, 0..=15) => hat(skin_x, skin_y),
+            (0..=15, 16..=31) => PANTS_COLOR,
+            (16..=39, 16..=31) => SHIRT_COLOR,
+            _ => SKIN_COLOR,
+        });
+    }
+
+    let mut png = vec![];
+    image::DynamicImage::ImageRgba8(skin)
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    png
+}
+
+fn hat_pixels(skin: &image::RgbaImage) -> impl Iterator<Item = &image::Rgba<u8>> {
+    let scale = skin.width() / 64;
+
+    skin.enumerate_pixels()
+        .filter(move |&(x, y, _)| x >= 32 * scale && y < 16 * scale)
+        .map(|(_, _, pixel)| pixel)
+}
+
+#[test]
+fn solid_hat_is_cleared() {
+    let skin = decode_skin(&legacy_skin(1, |_, _| HAT_COLOR), SkinModel::Classic).unwrap();
+
+    assert_eq!(skin.dimensions(), (64, 64));
+    assert!(hat_pixels(&skin).all(|pixel| pixel[3] == 0));
+}
+
+#[test]
+fn opaque_patterned_hat_is_cleared() {
+    let hat = |x: u32, _| if x < 48 { HAT_COLOR } else { SHIRT_COLOR };
+    let skin = decode_skin(&legacy_skin(1, hat), SkinModel::Classic).unwrap();
+
+    assert!(hat_pixels(&skin).all(|pixel| pixel[3] == 0));
+}
+
+#[test]
+fn hat_with_transparency_is_kept() {
+    // a real hat, the brim on the front face and nothing elsewhere
+    let hat = |x: u32, y: u32| if (40..48).contains(&x) && (8..10).contains(&y) { HAT_COLOR } else { [0, 0, 0, 0] };
+    let skin = decode_skin(&legacy_skin(1, hat), SkinModel::Classic).unwrap();
+
+    assert_eq!(skin.get_pixel(42, 8).0, HAT_COLOR);
+    assert_eq!(skin.get_pixel(42, 12)[3], 0);
+}
+
+#[test]
+fn transparency_below_hat_keeps_hat() {
+    // an opaque hat, with a see-through pixel on the right arm the Java client also checks
+    let mut skin = image::load_from_memory(&legacy_skin(1, |_, _| HAT_COLOR)).unwrap().into_rgba8();
+    skin.put_pixel(50, 24, image::Rgba([0, 0, 0, 0]));
+
+    let mut png = vec![];
+    image::DynamicImage::ImageRgba8(skin)
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let skin = decode_skin(&png, SkinModel::Classic).unwrap();
+
+    assert!(hat_pixels(&skin).all(|pixel| pixel.0 == HAT_COLOR));
+}
+
+#[test]
+fn hd_solid_hat_is_cleared() {
+    let skin = decode_skin(&legacy_skin(2, |_, _| HAT_COLOR), SkinModel::Classic).unwrap();
+
+    assert_eq!(skin.dimensions(), (128, 128));
+    assert!(hat_pixels(&skin).all(|pixel| pixel[3] == 0));
+}
+
+#[test]
+fn modern_solid_hat_is_kept() {
+    let mut skin = image::RgbaImage::from_pixel(64, 64, image::Rgba(SKIN_COLOR));
+    for y in 0..16 {
+        for x in 32..64 {
+            skin.put_pixel(x, y, image::Rgba(HAT_COLOR));
+        }
+    }
+
+    let mut png = vec![];
+    image::DynamicImage::ImageRgba8(skin)
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let skin = decode_skin(&png, SkinModel::Classic).unwrap();
+
+    assert!(hat_pixels(&skin).all(|pixel| pixel.0 == HAT_COLOR));
+}
+
+#[test]
+fn legacy_limbs_are_mirrored() {
+    let skin = decode_skin(&legacy_skin(1, |_, _| HAT_COLOR), SkinModel::Classic).unwrap();
+
+    // left leg and left arm front faces
+    assert_eq!(skin.get_pixel(20, 52).0, PANTS_COLOR);
+    assert_eq!(skin.get_pixel(36, 52).0, SKIN_COLOR);
+
+    // the left leg and arm sleeve areas stay empty
+    assert_eq!(skin.get_pixel(4, 52)[3], 0);
+    assert_eq!(skin.get_pixel(52, 52)[3], 0);
+}
+
+#[test]
+fn layout_conversion_keeps_opaque_hat() {
+    // armor textures use the legacy layout without the skin quirks
+    let texture = image::load_from_memory(&legacy_skin(1, |_, _| HAT_COLOR)).unwrap().into_rgba8();
+    let converted = convert_legacy_layout(&texture);
+
+    assert_eq!(converted.dimensions(), (64, 64));
+    assert!(hat_pixels(&converted).all(|pixel| pixel.0 == HAT_COLOR));
+    assert_eq!(converted.get_pixel(20, 52).0, PANTS_COLOR);
+}
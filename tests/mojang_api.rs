@@ -0,0 +1,272 @@
+//! Drives the skin download code against an in-process stand-in for Mojang's API
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::thread;
+
+use minecraft_skins::default_skins::default_skin_for_uuid;
+use minecraft_skins::error::Error;
+use minecraft_skins::skin_meta::SkinModel;
+use minecraft_skins::skin_provider::{MojangApi, SkinProvider, SkinUrl};
+
+const CLASSIC_UUID: &str = "853c80ef3c3749fdaa49938b674adae6";
+const SLIM_UUID: &str = "ec561538f3fd461daff5086b22154bce";
+/// Gets the slim Alex default skin
+const DEFAULT_UUID: &str = "069a79f444e94726a5befca90e38aaf5";
+/// Gets the classic Steve default skin
+const STEVE_UUID: &str = "05b6e6e307d4bedc51431193e6c3f339";
+const BAD_BASE64_UUID: &str = "61699b2ed3274a019f1e0ea8c3f06bc6";
+
+static STEVE_PNG: &[u8] = include_bytes!("../src/popup/steve.png");
+
+struct Response {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Response { status: 200, body: body.into() }
+    }
+
+    fn status(status: u16) -> Self {
+        Response { status, body: vec![] }
+    }
+}
+
+fn skin_png(color: [u8; 4]) -> Vec<u8> {
+    let skin = image::RgbaImage::from_pixel(64, 64, image::Rgba(color));
+
+    let mut png = vec![];
+    image::DynamicImage::ImageRgba8(skin)
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+
+    png
+}
+
+fn textures_property(base_url: &str, uuid: &str, name: &str, skin: Option<(&str, bool)>) -> String {
+    let skin = match skin {
+        Some((file, true)) => format!(
+            r#""SKIN": {{ "url": "{}/textures/{}", "metadata": {{ "model": "slim" }} }}"#,
+            base_url, file
+        ),
+        Some((file, false)) => format!(r#""SKIN": {{ "url": "{}/textures/{}" }}"#, base_url, file),
+        None => String::new(),
+    };
+
+    let textures = format!(
+        r#"{{ "timestamp": 1600000000000, "profileId": "{}", "profileName": "{}", "textures": {{ {} }} }}"#,
+        uuid, name, skin
+    );
+
+    base64::encode(textures)
+}
+
+fn session(uuid: &str, name: &str, textures: &str) -> String {
+    format!(
+        r#"{{ "id": "{}", "name": "{}", "properties": [ {{ "name": "textures", "value": "{}" }} ] }}"#,
+        uuid, name, textures
+    )
+}
+
+/// Fixture responses for a handful of players, keyed by path
+fn routes(base_url: &str) -> HashMap<String, Response> {
+    let mut routes = HashMap::new();
+
+    let players = [
+        ("Classic", CLASSIC_UUID, Some(("classic.png", false))),
+        ("Slim", SLIM_UUID, Some(("slim.png", true))),
+        ("DefaultSkin", DEFAULT_UUID, None),
+        ("DefaultSteve", STEVE_UUID, None),
+    ];
+
+    for &(name, uuid, skin) in &players {
+        routes.insert(
+            format!("/users/profiles/minecraft/{}", name),
+            Response::ok(format!(r#"{{ "name": "{}", "id": "{}" }}"#, name, uuid)),
+        );
+        routes.insert(
+            format!("/session/minecraft/profile/{}", uuid),
+            Response::ok(session(uuid, name, &textures_property(base_url, uuid, name, skin))),
+        );
+    }
+
+    routes.insert(
+        "/users/profiles/minecraft/BadBase64".to_owned(),
+        Response::ok(format!(r#"{{ "name": "BadBase64", "id": "{}" }}"#, BAD_BASE64_UUID)),
+    );
+    routes.insert(
+        format!("/session/minecraft/profile/{}", BAD_BASE64_UUID),
+        Response::ok(session(BAD_BASE64_UUID, "BadBase64", "!!not base64!!")),
+    );
+
+    routes.insert("/users/profiles/minecraft/RateLimited".to_owned(), Response::status(429));
+    routes.insert("/users/profiles/minecraft/NoContent".to_owned(), Response::status(204));
+
+    routes.insert("/textures/classic.png".to_owned(), Response::ok(skin_png([255, 0, 0, 255])));
+    routes.insert("/textures/slim.png".to_owned(), Response::ok(skin_png([0, 255, 0, 255])));
+    routes.insert("/skins/Direct.png".to_owned(), Response::ok(skin_png([0, 0, 255, 255])));
+
+    routes
+}
+
+/// Start a fake API server on a free port, returning its base url. Anything not in the
+/// fixtures gets a 404 like an unknown player does.
+fn start_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let routes = routes(&base_url);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+
+            let mut reader = BufReader::new(&stream);
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue
+            }
+
+            // skip the headers
+            let mut line = String::new();
+            while reader.read_line(&mut line).map(|len| len > 2).unwrap_or(false) {
+                line.clear();
+            }
+
+            let path = request_line.split(' ').nth(1).unwrap_or("/");
+            let not_found = Response::status(404);
+            let response = routes.get(path).unwrap_or(&not_found);
+
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {} Fixture\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                response.status,
+                response.body.len()
+            );
+            let _ = stream.write_all(&response.body);
+        }
+    });
+
+    base_url
+}
+
+fn provider(base_url: &str) -> MojangApi {
+    MojangApi {
+        api_url: base_url.to_owned(),
+        session_url: base_url.to_owned(),
+        default_skins_dir: None,
+    }
+}
+
+#[test]
+fn downloads_classic_skin() {
+    let base_url = start_server();
+
+    let skin = provider(&base_url).fetch_skin("Classic").unwrap();
+
+    assert_eq!(skin.png, skin_png([255, 0, 0, 255]));
+    assert_eq!(skin.model, SkinModel::Classic);
+}
+
+#[test]
+fn downloads_slim_skin() {
+    let base_url = start_server();
+
+    let skin = provider(&base_url).fetch_skin("Slim").unwrap();
+
+    assert_eq!(skin.png, skin_png([0, 255, 0, 255]));
+    assert_eq!(skin.model, SkinModel::Slim);
+}
+
+#[test]
+fn unknown_player() {
+    let base_url = start_server();
+
+    let result = provider(&base_url).fetch_skin("Nobody");
+    assert!(matches!(result, Err(Error::PlayerNotFound)));
+
+    let result = provider(&base_url).fetch_skin("NoContent");
+    assert!(matches!(result, Err(Error::PlayerNotFound)));
+}
+
+#[test]
+fn rate_limited() {
+    let base_url = start_server();
+
+    let result = provider(&base_url).fetch_skin("RateLimited");
+
+    assert!(matches!(result, Err(Error::Status(429))));
+}
+
+#[test]
+fn malformed_textures() {
+    let base_url = start_server();
+
+    let result = provider(&base_url).fetch_skin("BadBase64");
+
+    assert!(matches!(result, Err(Error::InvalidProfile(_))));
+}
+
+#[test]
+fn default_steve_is_bundled() {
+    let base_url = start_server();
+
+    let skin = provider(&base_url).fetch_skin("DefaultSteve").unwrap();
+
+    assert_eq!(skin.png, STEVE_PNG);
+    assert_eq!(skin.model, SkinModel::Classic);
+}
+
+#[test]
+fn missing_default_skin() {
+    let base_url = start_server();
+
+    let result = provider(&base_url).fetch_skin("DefaultSkin");
+
+    assert!(matches!(result, Err(Error::MissingDefaultSkin(path)) if path == "slim/alex.png"));
+}
+
+#[test]
+fn default_skin_from_folder() {
+    let base_url = start_server();
+
+    let default = default_skin_for_uuid(DEFAULT_UUID).unwrap();
+    let png = skin_png([12, 34, 56, 255]);
+
+    let dir: PathBuf = std::env::temp_dir().join(format!("minecraft_skins_defaults_{}", std::process::id()));
+    let path = dir.join(default.asset_path());
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, &png).unwrap();
+
+    let mut provider = provider(&base_url);
+    provider.default_skins_dir = Some(dir.clone());
+    let skin = provider.fetch_skin("DefaultSkin").unwrap();
+
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(skin.png, png);
+    assert_eq!(skin.model, default.model);
+}
+
+#[test]
+fn direct_url() {
+    let base_url = start_server();
+
+    let provider = SkinUrl {
+        url: format!("{}/skins/{{username}}.png", base_url),
+        model: SkinModel::Slim,
+    };
+    let skin = provider.fetch_skin("Direct").unwrap();
+
+    assert_eq!(skin.png, skin_png([0, 0, 255, 255]));
+    assert_eq!(skin.model, SkinModel::Slim);
+
+    let result = provider.fetch_skin("Missing");
+    assert!(matches!(result, Err(Error::PlayerNotFound)));
+}
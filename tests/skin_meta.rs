@@ -0,0 +1,68 @@
+//! Reading a skin's json file when picking its color correction
+
+use std::fs;
+use std::path::PathBuf;
+
+use minecraft_skins::color_correct::ColorCorrection;
+use minecraft_skins::config::Config;
+use minecraft_skins::error::Error;
+use minecraft_skins::skin_meta::{SkinMeta, SkinModel};
+
+/// A skin png path in a folder of its own with `json` next to it
+fn skin_with_meta(name: &str, json: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("minecraft_skins_meta_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("skin.png");
+    fs::write(path.with_extension("json"), json).unwrap();
+
+    path
+}
+
+fn skin() -> image::RgbaImage {
+    image::RgbaImage::from_pixel(64, 64, image::Rgba([30, 30, 30, 255]))
+}
+
+#[test]
+fn invalid_meta_is_not_overwritten() {
+    let json = r#"{ "model": "slim", "auto_exposure": "#;
+    let path = skin_with_meta("invalid", json);
+
+    let config = Config { auto_exposure: true, ..Config::default() };
+    let result = config.skin_color_correction(&path, &skin());
+    let saved = fs::read_to_string(path.with_extension("json")).unwrap();
+
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+
+    assert!(matches!(result, Err(Error::InvalidMeta(_))));
+    assert_eq!(saved, json);
+}
+
+#[test]
+fn saved_auto_exposure_needs_auto_exposure_on() {
+    let path = skin_with_meta("saved", r#"{ "auto_exposure": { "exposure": 2.0, "contrast": 1.0 } }"#);
+
+    let off = Config::default().skin_color_correction(&path, &skin()).unwrap();
+    let on = Config { auto_exposure: true, ..Config::default() }.skin_color_correction(&path, &skin()).unwrap();
+
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+
+    // the default profile is a lookup table, auto exposure uses the linear profile
+    assert!(matches!(off, ColorCorrection::Table(_)));
+    assert!(matches!(on, ColorCorrection::Linear { .. }));
+}
+
+#[test]
+fn downloading_again_keeps_settings() {
+    let path = skin_with_meta("download", r#"{ "model": "classic", "color_profile": "warm", "overlay": { "hat": false } }"#);
+
+    SkinMeta::save_model(&path, SkinModel::Slim).unwrap();
+    let meta = SkinMeta::load(&path);
+
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+
+    let meta = meta.unwrap();
+    assert_eq!(meta.model, SkinModel::Slim);
+    assert_eq!(meta.color_profile.as_deref(), Some("warm"));
+    assert!(!meta.overlay.hat);
+}
@@ -0,0 +1,77 @@
+//! Handing out costume slots to players so different skins on the same costume don't collide
+
+use std::path::Path;
+
+use minecraft_skins::slot_assignment::SlotAssignments;
+
+const PLAYER_1: usize = 1;
+const PLAYER_2: usize = 2;
+
+#[test]
+fn two_players_same_costume_different_skins() {
+    let mut slots = SlotAssignments::new();
+
+    assert_eq!(slots.assign(PLAYER_1, 0, Some(Path::new("alice.png"))), 0);
+    assert_eq!(slots.assign(PLAYER_2, 0, Some(Path::new("bob.png"))), 1);
+}
+
+#[test]
+fn two_players_same_costume_same_skin_share_a_slot() {
+    let mut slots = SlotAssignments::new();
+
+    assert_eq!(slots.assign(PLAYER_1, 3, Some(Path::new("alice.png"))), 3);
+    assert_eq!(slots.assign(PLAYER_2, 3, Some(Path::new("alice.png"))), 3);
+
+    // the slot stays in use until both players leave it
+    assert_eq!(slots.assign(PLAYER_1, 3, Some(Path::new("bob.png"))), 0);
+    assert_eq!(slots.assign(PLAYER_2, 3, Some(Path::new("carol.png"))), 3);
+}
+
+#[test]
+fn repick_keeps_own_slot() {
+    let mut slots = SlotAssignments::new();
+
+    assert_eq!(slots.assign(PLAYER_1, 0, Some(Path::new("alice.png"))), 0);
+    assert_eq!(slots.assign(PLAYER_1, 0, Some(Path::new("bob.png"))), 0);
+    assert_eq!(slots.assign(PLAYER_1, 0, None), 0);
+}
+
+#[test]
+fn repick_releases_moved_slot() {
+    let mut slots = SlotAssignments::new();
+
+    assert_eq!(slots.assign(PLAYER_1, 0, Some(Path::new("alice.png"))), 0);
+    assert_eq!(slots.assign(PLAYER_2, 0, Some(Path::new("bob.png"))), 1);
+
+    // player 2 backs out and picks again many times without running out of slots
+    for i in 0..20 {
+        let skin = format!("skin_{}.png", i);
+        assert_eq!(slots.assign(PLAYER_2, 0, Some(Path::new(&skin))), 1);
+    }
+
+    // after player 1 leaves costume 0, player 2 gets it back on their next pick
+    assert_eq!(slots.assign(PLAYER_1, 5, Some(Path::new("alice.png"))), 5);
+    assert_eq!(slots.assign(PLAYER_2, 0, Some(Path::new("bob.png"))), 0);
+}
+
+#[test]
+fn release_frees_slot() {
+    let mut slots = SlotAssignments::new();
+
+    assert_eq!(slots.assign(PLAYER_1, 0, Some(Path::new("alice.png"))), 0);
+    slots.release(PLAYER_1);
+    assert_eq!(slots.assign(PLAYER_2, 0, Some(Path::new("bob.png"))), 0);
+}
+
+#[test]
+fn repicks_are_recognized_until_cleared() {
+    let mut slots = SlotAssignments::new();
+
+    assert!(!slots.has_player(PLAYER_1));
+    slots.assign(PLAYER_1, 0, Some(Path::new("alice.png")));
+    assert!(slots.has_player(PLAYER_1));
+    assert!(!slots.has_player(PLAYER_2));
+
+    slots.clear();
+    assert!(!slots.has_player(PLAYER_1));
+}
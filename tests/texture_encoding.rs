@@ -0,0 +1,302 @@
+//! Writing textures as swizzled, block compressed nutexbs and reading them back
+
+use minecraft_skins::bcn;
+use minecraft_skins::color_correct::ColorCorrection;
+use minecraft_skins::mipmaps::generate_mipmaps;
+use minecraft_skins::nutexb_file::{self, read_rgba_image, write_nutexb, NutexbFooter, TextureFormat, FOOTER_SIZE};
+use minecraft_skins::skin_texture::{encode_skin_nutexb, move_footer_to_end, write_skin_nutexb, TextureConfig, MAX_FILE_SIZE};
+use minecraft_skins::swizzle;
+
+const FORMATS: [TextureFormat; 4] = [TextureFormat::Rgba8, TextureFormat::Bc1, TextureFormat::Bc3, TextureFormat::Bc7];
+
+/// Sizes in blocks, with sizes that aren't a multiple of a GOB
+const SWIZZLE_SIZES: [(usize, usize); 6] = [(1, 1), (4, 4), (16, 8), (64, 64), (100, 37), (256, 256)];
+
+/// Flat 8x8 patches of color like an upscaled skin, a gray ramp across the right half and a
+/// fully transparent strip along the bottom, each starting on a block edge
+fn test_image(width: u32, height: u32) -> image::RgbaImage {
+    let mut image = image::RgbaImage::new(width, height);
+    let (ramp_x, transparent_y) = (width / 8 * 4, (height - 8) / 4 * 4);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        *pixel = image::Rgba(if y >= transparent_y {
+            [0, 0, 0, 0]
+        } else if x >= ramp_x {
+            let value = (x * 255 / width) as u8;
+            [value, value, value, 255]
+        } else {
+            let patch = (x / 8) + (y / 8) * 4;
+            [(patch * 70 % 256) as u8, (patch * 150 % 256) as u8, (patch * 40 % 256) as u8, 255]
+        });
+    }
+
+    image
+}
+
+/// Bytes that are different at every offset of a small surface, so any misplaced byte shows
+fn numbered_bytes(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// Largest difference of any channel of any pixel
+fn max_error(x: &image::RgbaImage, y: &image::RgbaImage) -> u8 {
+    x.as_raw().iter().zip(y.as_raw()).map(|(x, y)| x.abs_diff(*y)).max().unwrap()
+}
+
+fn round_trip(image: &image::RgbaImage, format: TextureFormat) -> image::RgbaImage {
+    let mut nutexb = vec![];
+    write_nutexb("def_pickel_001_col", std::slice::from_ref(image), format, &mut nutexb).unwrap();
+
+    read_rgba_image(&nutexb).unwrap()
+}
+
+#[test]
+fn formats_round_trip() {
+    for (width, height) in [(64, 64), (128, 128), (42, 22)] {
+        let image = test_image(width, height);
+
+        for format in FORMATS {
+            let decoded = round_trip(&image, format);
+            assert_eq!(decoded.dimensions(), image.dimensions());
+
+            let bound = match format {
+                TextureFormat::Rgba8 => 0,
+                TextureFormat::Bc7 => 2,
+                TextureFormat::Bc1 | TextureFormat::Bc3 => 8,
+            };
+
+            let error = max_error(&image, &decoded);
+            assert!(error <= bound, "{:?} at {}x{} is off by {}", format, width, height, error);
+        }
+    }
+}
+
+fn write_mip_chain(format: TextureFormat) -> (Vec<image::RgbaImage>, Vec<u8>) {
+    let mips = generate_mipmaps(test_image(64, 64));
+
+    let mut nutexb = vec![];
+    let size = write_nutexb("def_pickel_001_col", &mips, format, &mut nutexb).unwrap();
+    assert_eq!(size, nutexb.len());
+
+    (mips, nutexb)
+}
+
+#[test]
+fn mip_chain_header() {
+    for format in FORMATS {
+        let (mips, nutexb) = write_mip_chain(format);
+        let footer = NutexbFooter::read(&nutexb).unwrap();
+
+        assert_eq!(mips.len(), 7);
+        assert_eq!(footer.mip_count as usize, mips.len());
+        assert_eq!(footer.mip_sizes.len(), mips.len());
+        assert_eq!((footer.width, footer.height), (64, 64));
+        assert_eq!(footer.format, format.nutexb_format());
+
+        assert_eq!(footer.mip_sizes.iter().sum::<u32>(), footer.data_size);
+        assert_eq!(footer.data_size as usize, nutexb_file::data_size(format, 64, 64, mips.len()));
+        assert_eq!(nutexb.len(), footer.data_size as usize + FOOTER_SIZE);
+    }
+}
+
+#[test]
+fn mip_levels_at_offsets() {
+    let (mips, nutexb) = write_mip_chain(TextureFormat::Rgba8);
+    let footer = NutexbFooter::read(&nutexb).unwrap();
+    let block_height = swizzle::block_height_mip0(64);
+
+    // each level starts right after the one before it
+    let mut offset = 0;
+    for (mip, &size) in mips.iter().zip(&footer.mip_sizes) {
+        let (width, height) = (mip.width() as usize, mip.height() as usize);
+        let level = &nutexb[offset..offset + size as usize];
+
+        let pixels = swizzle::deswizzle(level, width, height, 4, swizzle::mip_block_height(height, block_height)).unwrap();
+        assert_eq!(&pixels, mip.as_raw(), "level {}x{}", width, height);
+
+        offset += size as usize;
+    }
+
+    assert_eq!(offset + FOOTER_SIZE, nutexb.len());
+}
+
+#[test]
+fn largest_texture_fits() {
+    for format in FORMATS {
+        let config = TextureConfig { size: 1024, mipmaps: true, format, ..TextureConfig::default() };
+        assert!(config.max_file_size() <= MAX_FILE_SIZE);
+
+        let mut data = vec![0; config.max_file_size()];
+        write_skin_nutexb(test_image(64, 64), &config, &ColorCorrection::default(), &mut data).unwrap();
+
+        let footer = NutexbFooter::read(&data).unwrap();
+        assert!(footer.data_size as usize + FOOTER_SIZE <= MAX_FILE_SIZE, "{:?}", format);
+        assert!(footer.mip_count > 1, "{:?}", format);
+    }
+}
+
+#[test]
+fn format_is_one_byte() {
+    let (mips, mut nutexb) = write_mip_chain(TextureFormat::Bc7);
+    let footer_start = nutexb.len() - FOOTER_SIZE;
+    assert_eq!(nutexb[footer_start + 0x90], nutexb_file::BC7_SRGB);
+
+    // the byte after the format isn't part of it
+    nutexb[footer_start + 0x91] = 0;
+    let footer = NutexbFooter::read(&nutexb).unwrap();
+    assert_eq!((footer.format, footer.unk), (nutexb_file::BC7_SRGB, 0));
+
+    let error = max_error(&mips[0], &read_rgba_image(&nutexb).unwrap());
+    assert!(error <= 2, "off by {}", error);
+}
+
+#[test]
+fn mipmaps_off_writes_one_level() {
+    for format in FORMATS {
+        let config = TextureConfig { size: 1024, mipmaps: false, format, ..TextureConfig::default() };
+
+        let mut nutexb = vec![];
+        let size = encode_skin_nutexb(test_image(64, 64), &config, &ColorCorrection::default(), &mut nutexb).unwrap();
+        assert_eq!(size, nutexb.len());
+
+        let footer = NutexbFooter::read(&nutexb).unwrap();
+        assert_eq!(footer.mip_count, 1, "{:?}", format);
+        assert_eq!(footer.mip_sizes.len(), 1, "{:?}", format);
+        assert_eq!((footer.width, footer.height), (1024, 1024), "{:?}", format);
+    }
+}
+
+#[test]
+fn footer_moved_to_end() {
+    for format in FORMATS {
+        let (_, nutexb) = write_mip_chain(format);
+        let real_size = nutexb.len();
+
+        let mut data = nutexb.clone();
+        data.resize(real_size + 0x1000, 0);
+        move_footer_to_end(&mut data, real_size).unwrap();
+
+        assert_eq!(&data[data.len() - FOOTER_SIZE..], &nutexb[real_size - FOOTER_SIZE..]);
+
+        let (footer, moved) = (NutexbFooter::read(&nutexb).unwrap(), NutexbFooter::read(&data).unwrap());
+        assert_eq!(moved.mip_sizes, footer.mip_sizes);
+        assert_eq!(moved.data_size, footer.data_size);
+        assert_eq!(read_rgba_image(&data).unwrap(), read_rgba_image(&nutexb).unwrap());
+
+        // a buffer too small for the file is an error, not a truncated texture
+        let mut too_small = nutexb.clone();
+        too_small.truncate(real_size - 1);
+        assert!(move_footer_to_end(&mut too_small, real_size).is_err());
+    }
+}
+
+#[test]
+fn swizzle_round_trip() {
+    for (width, height) in SWIZZLE_SIZES {
+        for bytes_per_block in [4, 8, 16] {
+            for block_height in [1, 2, 4, 8, 16] {
+                let linear = numbered_bytes(width * height * bytes_per_block);
+
+                let swizzled = swizzle::swizzle(&linear, width, height, bytes_per_block, block_height);
+                assert_eq!(swizzled.len(), swizzle::swizzled_size(width, height, bytes_per_block, block_height));
+
+                let deswizzled = swizzle::deswizzle(&swizzled, width, height, bytes_per_block, block_height);
+                assert_eq!(deswizzled.as_ref(), Some(&linear), "{}x{} {} bytes per block, block height {}", width, height, bytes_per_block, block_height);
+            }
+        }
+    }
+}
+
+#[test]
+fn swizzle_gob_layout() {
+    // 16x4 RGBA pixels is 64 bytes by 4 rows, the top half of a GOB
+    let linear = numbered_bytes(64 * 4);
+    let swizzled = swizzle::swizzle(&linear, 16, 4, 4, 1);
+
+    // a GOB is made of 16 byte by 2 row sectors, two wide then down each 32 byte half
+    let linear_byte = |x: usize, y: usize| linear[y * 64 + x];
+    assert_eq!(swizzled[0], linear_byte(0, 0));
+    assert_eq!(swizzled[16], linear_byte(0, 1));
+    assert_eq!(swizzled[32], linear_byte(16, 0));
+    assert_eq!(swizzled[64], linear_byte(0, 2));
+    assert_eq!(swizzled[256], linear_byte(32, 0));
+}
+
+#[test]
+fn deswizzle_too_small() {
+    let swizzled = swizzle::swizzle(&numbered_bytes(64 * 64 * 4), 64, 64, 4, 8);
+
+    assert_eq!(swizzle::deswizzle(&swizzled[..swizzled.len() - 1], 64, 64, 4, 8), None);
+}
+
+/// A single 4x4 block
+fn block(pixel: impl Fn(u32, u32) -> [u8; 4]) -> image::RgbaImage {
+    image::RgbaImage::from_fn(4, 4, |x, y| image::Rgba(pixel(x, y)))
+}
+
+fn bc_round_trip(image: &image::RgbaImage, format: TextureFormat) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+    let pixels = match format {
+        TextureFormat::Bc1 => bcn::decompress_bc1(&bcn::compress_bc1(image), width, height),
+        TextureFormat::Bc3 => bcn::decompress_bc3(&bcn::compress_bc3(image), width, height),
+        TextureFormat::Bc7 => bcn::decompress_bc7(&bcn::compress_bc7(image), width, height),
+        TextureFormat::Rgba8 => unreachable!(),
+    };
+
+    image::RgbaImage::from_raw(width, height, pixels).unwrap()
+}
+
+#[test]
+fn block_compression_error() {
+    let blocks = [
+        ("solid", block(|_, _| [198, 150, 128, 255]), [4, 4, 1]),
+        ("gradient", block(|x, _| [(x * 60) as u8, 20 + (x * 40) as u8, 200 - (x * 40) as u8, 255]), [6, 6, 2]),
+    ];
+
+    for (name, image, bounds) in blocks {
+        for (&format, bound) in [TextureFormat::Bc1, TextureFormat::Bc3, TextureFormat::Bc7].iter().zip(bounds) {
+            let error = max_error(&image, &bc_round_trip(&image, format));
+            assert!(error <= bound, "{} block in {:?} is off by {}", name, format, error);
+        }
+    }
+}
+
+#[test]
+fn block_compression_alpha() {
+    // smooth alpha is kept by BC3 and BC7, BC3 has 8 levels between the least and most opaque
+    let image = block(|x, _| [40, 90, 160, (x * 85) as u8]);
+    for (format, bound) in [(TextureFormat::Bc3, 255 / 7 / 2 + 1), (TextureFormat::Bc7, 2)] {
+        let error = max_error(&image, &bc_round_trip(&image, format));
+        assert!(error <= bound, "alpha block in {:?} is off by {}", format, error);
+    }
+
+    // BC1 makes pixels either opaque or fully transparent
+    let image = block(|x, _| if x < 2 { [0, 0, 0, 0] } else { [40, 90, 160, 255] });
+    let decoded = bc_round_trip(&image, TextureFormat::Bc1);
+    for (pixel, decoded) in image.pixels().zip(decoded.pixels()) {
+        assert_eq!(pixel[3], decoded[3]);
+    }
+}
+
+#[test]
+fn reserved_size_fits_every_config() {
+    let skin = test_image(64, 64);
+
+    for size in [64, 100, 128, 256, 512, 1024] {
+        for mipmaps in [false, true] {
+            for format in FORMATS {
+                let config = TextureConfig { size, mipmaps, format, ..TextureConfig::default() };
+
+                // the plugin reserves exactly this much for each slot
+                let mut data = vec![0; config.max_file_size()];
+                let result = write_skin_nutexb(skin.clone(), &config, &ColorCorrection::default(), &mut data);
+                assert!(result.is_ok(), "{:?} doesn't fit: {:?}", config, result);
+
+                // only a full size uncompressed texture with mipmaps is shrunk to fit
+                let footer = NutexbFooter::read(&data).unwrap();
+                let width = (0..5).map(|i| 64 << i).filter(|&width| width <= size).max().unwrap();
+                assert!(footer.width == width || config.max_file_size() == MAX_FILE_SIZE, "{:?} is {} wide", config, footer.width);
+            }
+        }
+    }
+}
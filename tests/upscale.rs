@@ -0,0 +1,29 @@
+//! Smoothing skins with Scale2x without mixing faces that are only next to each other in the texture
+
+use minecraft_skins::upscale::{upscale, UpscaleFilter};
+
+const RED: image::Rgba<u8> = image::Rgba([200, 30, 30, 255]);
+const BLUE: image::Rgba<u8> = image::Rgba([30, 30, 200, 255]);
+
+#[test]
+fn scale2x_keeps_faces_apart() {
+    // the front of the head is blue, the faces above and left of it in the texture are red,
+    // which would round off its top left corner if they were treated as neighbors
+    let mut skin = image::RgbaImage::new(64, 64);
+    for (x, y, pixel) in skin.enumerate_pixels_mut() {
+        *pixel = match (x, y) {
+            (8..=15, 8..=15) => BLUE,
+            (0..=7, 8..=15) | (8..=15, 0..=7) => RED,
+            _ => image::Rgba([0, 0, 0, 0]),
+        };
+    }
+
+    let scaled = upscale(skin, 128, UpscaleFilter::Scale2x);
+
+    assert_eq!(scaled.width(), 128);
+    for y in 16..32 {
+        for x in 16..32 {
+            assert_eq!(*scaled.get_pixel(x, y), BLUE, "at {}, {}", x, y);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Saving recolored variants of a skin as skins of their own
+
+use std::fs;
+
+use minecraft_skins::recolor::{save_variants, skin_folder, variant_path};
+use minecraft_skins::selections::SavedSelections;
+
+#[test]
+fn variants_dont_overwrite_skins() {
+    let dir = std::env::temp_dir().join(format!("minecraft_skins_variants_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let skin = image::RgbaImage::from_pixel(64, 64, image::Rgba([200, 30, 30, 255]));
+    let skin_path = dir.join("Foo.png");
+    skin.save(&skin_path).unwrap();
+    fs::write(dir.join("Foo.json"), r#"{ "variants": { "red": [{ "regions": ["body"], "type": "hue_shift", "degrees": 120 }] } }"#).unwrap();
+
+    // a real skin named like the variant used to be
+    let other_path = dir.join("Foo_red.png");
+    let other = image::RgbaImage::from_pixel(64, 64, image::Rgba([10, 20, 30, 255]));
+    other.save(&other_path).unwrap();
+
+    let paths = save_variants(&skin_path);
+    let other_after = image::open(&other_path).unwrap().into_rgba8();
+    let variant_exists = variant_path(&skin_path, "red").is_file();
+
+    let _ = fs::remove_dir_all(&dir);
+
+    assert_eq!(paths.unwrap(), vec![dir.join("variants").join("Foo").join("red.png")]);
+    assert!(variant_exists);
+    assert_eq!(other_after, other);
+}
+
+#[test]
+fn variants_use_their_skins_folder() {
+    let dir = std::path::Path::new("minecraft_skins");
+    let skin_path = dir.join("Foo.png");
+
+    assert_eq!(skin_folder(&skin_path), dir);
+    assert_eq!(skin_folder(&variant_path(&skin_path, "red")), dir);
+}
+
+#[test]
+fn picked_variant_is_restored() {
+    let dir = std::env::temp_dir().join(format!("minecraft_skins_variant_pick_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // a real skin with the same file name as the variant
+    let skin_path = dir.join("Foo.png");
+    let variant = variant_path(&skin_path, "red");
+    fs::create_dir_all(variant.parent().unwrap()).unwrap();
+    for path in &[&skin_path, &dir.join("red.png"), &variant] {
+        image::RgbaImage::new(64, 64).save(path).unwrap();
+    }
+
+    let mut saved = SavedSelections::load(&dir);
+    saved.set(2, Some(&variant));
+    saved.save().unwrap();
+
+    let restored = SavedSelections::load(&dir).skin_path(2);
+
+    let _ = fs::remove_dir_all(&dir);
+
+    assert_eq!(restored, Some(variant));
+}